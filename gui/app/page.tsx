@@ -8,12 +8,17 @@ import { NormalisableRange } from "@/lib/utils";
 import React, { useEffect, useState } from "react";
 
 import { KneeKnob } from "@/components/knobs/KneeKnob";
+import { RangeKnob } from "@/components/knobs/RangeKnob";
 import { DBKnob } from "@/components/knobs/generic/DBKnob";
 import { sendToPlugin } from "@/lib";
 import { PercentKnob } from "@/components/knobs/generic/PercentKnob";
+import { FrequencyKnob } from "@/components/knobs/generic/FrequencyKnob";
+import { ToggleButton } from "@/components/ToggleButton";
+import { EmphasisKnob } from "@/components/knobs/EmphasisKnob";
 import { Message } from "@/bindings/Messages";
 import { AudioGraph } from "@/components/AudioGraph";
 import { GRMeter } from "@/components/GRMeter";
+import { BandGRMeter } from "@/components/BandGRMeter";
 import { useSampleRate } from "@/hooks";
 
 export default function Home() {
@@ -34,10 +39,15 @@ export default function Home() {
   const [rmsMix, setRmsMix] = useState(0);
   const [threshold, setThreshold] = useState(0);
   const [knee, setKnee] = useState(0);
+  const [range, setRange] = useState(0);
   const [releaseTime, setReleaseTime] = useState(0);
+  const [holdTime, setHoldTime] = useState(0);
   const [rmsLen, setRmsLen] = useState(0);
   const [lookahead, setLookahead] = useState(0);
   const [attack, setAttack] = useState(0);
+  const [deEsserFrequency, setDeEsserFrequency] = useState(0);
+  const [attackEmphasis, setAttackEmphasis] = useState(0);
+  const [sustainEmphasis, setSustainEmphasis] = useState(0);
   return (
     <main className="relative main-bg w-screen h-screen overflow-hidden px-3 py-5 text-[#180619] ">
       <p className="absolute text-xs bottom-0 right-0 opacity-50">
@@ -67,6 +77,16 @@ export default function Home() {
           value={releaseTime}
           setValue={setReleaseTime}
         />
+        <TimeKnob
+          label="HOLD"
+          minValue={0}
+          maxValue={0.5}
+          defaultValue={0}
+          range={new NormalisableRange(0, 0.5, 0.05)}
+          parameter="HoldTime"
+          value={holdTime}
+          setValue={setHoldTime}
+        />
       </div>
       <div className="h-[50%] flex justify-between items-center gap-3">
         <DBKnob
@@ -80,9 +100,11 @@ export default function Home() {
         <div className="flex gap-3 justify-center items-center">
           <ThresholdKnob value={threshold} setValue={setThreshold} />
           {/* WOWOWOWOWOW */}
-          <AudioGraph dryWet={dryWet} threshold={threshold} knee={knee} />
+          <AudioGraph dryWet={dryWet} />
           <GRMeter />
+          <BandGRMeter />
           <RatioKnob />
+          <RangeKnob value={range} setValue={setRange} />
         </div>
         {/* this div contains output-related knobs */}
         <div className="text-center ">
@@ -139,15 +161,51 @@ export default function Home() {
               <TimeKnob
                 label="LKAHD"
                 minValue={0}
-                maxValue={rmsLen}
+                maxValue={0.1}
                 defaultValue={0}
-                range={new NormalisableRange(0, rmsLen, rmsLen / 2)}
+                range={new NormalisableRange(0, 0.1, 0.02)}
                 parameter="Lookahead"
                 value={lookahead}
                 setValue={setLookahead}
               />
             </div>
           </div>
+          {/* DE-ESSER CONTROLS */}
+          <div className="text-center">
+            <p>DE-ESS</p>
+            <div className="flex gap-3 items-center">
+              <FrequencyKnob
+                label="FREQ"
+                minValue={20}
+                maxValue={20000}
+                defaultValue={6500}
+                range={new NormalisableRange(20, 20000, 1000)}
+                parameter="DeEsserFrequency"
+                value={deEsserFrequency}
+                setValue={setDeEsserFrequency}
+              />
+              <ToggleButton label="LISTEN" parameter="DeEsserListen" />
+            </div>
+          </div>
+          {/* TRANSIENT SHAPER CONTROLS */}
+          <div className="text-center">
+            <p>TRANSIENT</p>
+            <div className="flex gap-3 items-center">
+              <EmphasisKnob
+                label="ATK EMPH"
+                parameter="AttackEmphasis"
+                value={attackEmphasis}
+                setValue={setAttackEmphasis}
+              />
+              <EmphasisKnob
+                label="SUS EMPH"
+                parameter="SustainEmphasis"
+                value={sustainEmphasis}
+                setValue={setSustainEmphasis}
+              />
+              <ToggleButton label="ON" parameter="TransientShaper" />
+            </div>
+          </div>
         </div>
       </div>
     </main>
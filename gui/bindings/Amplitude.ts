@@ -1,3 +1,7 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 
-export type Amplitude = { pre_amplitude: number, post_amplitude: number, amt_reduced: number, };
+export type Amplitude = { pre_amplitude: number, post_amplitude: number, amt_reduced: number, 
+/**
+ * The gain reduction of each band, from low to high. This is empty unless multiband is on.
+ */
+band_reduced: Array<number>, };
@@ -1,9 +1,11 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 import type { Amplitude } from "./Amplitude";
 import type { Parameter } from "./Parameter";
+import type { TransferCurve } from "./TransferCurve";
 
 export type Message =
   | "Init"
   | "WindowClosed"
   | { ParameterUpdate: Parameter }
-  | { Amplitude: Amplitude };
+  | { Amplitude: Amplitude }
+  | { TransferCurve: TransferCurve };
@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * The static curve of the compressor, sampled so the GUI can draw it.
+ * Both fields are **in decibels**, and `output_db[i]` is the output level for `input_db[i]`.
+ */
+export type TransferCurve = { input_db: Array<number>, output_db: Array<number>, };
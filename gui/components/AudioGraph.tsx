@@ -1,5 +1,11 @@
 import type { Amplitude as AmplitudeMessage } from "@/bindings/Amplitude";
-import { useAmplitudeUpdate, useDecayFactor, useSampleRate } from "@/hooks";
+import type { TransferCurve } from "@/bindings/TransferCurve";
+import {
+  useAmplitudeUpdate,
+  useDecayFactor,
+  useSampleRate,
+  useTransferCurve,
+} from "@/hooks";
 import { gainToDb } from "@/lib/utils";
 import { MutableRefObject, useEffect, useRef, useState } from "react";
 
@@ -7,12 +13,9 @@ import { MutableRefObject, useEffect, useRef, useState } from "react";
 // documentation/comments
 // REFACTOR!
 
-export function AudioGraph(props: {
-  dryWet: number;
-  threshold: number;
-  knee: number;
-}) {
-  const { dryWet, threshold, knee } = props;
+export function AudioGraph(props: { dryWet: number }) {
+  const { dryWet } = props;
+  const curve = useTransferCurve();
 
   const decayFactor = useDecayFactor(100);
 
@@ -82,9 +85,8 @@ export function AudioGraph(props: {
         postAmplitudeBuffer,
         `rgba(180, 39, 112, ${Math.max(dryWet, 0.25)})`
       );
-      // add threshold line
-      drawThresholdLine(ctx, threshold, meterWidth, meterHeight);
-      drawKnee(ctx, threshold, knee, meterWidth, meterHeight);
+      // add the static curve (this also shows the threshold and knee)
+      drawTransferCurve(ctx, curve, meterWidth, meterHeight);
       animationRequest = requestAnimationFrame(draw);
     };
     animationRequest = requestAnimationFrame(draw);
@@ -92,7 +94,7 @@ export function AudioGraph(props: {
     return () => {
       cancelAnimationFrame(animationRequest);
     };
-  }, [dryWet, threshold, knee]);
+  }, [dryWet, curve]);
 
   return (
     <div>
@@ -132,40 +134,26 @@ function drawGain(
   ctx.fill();
 }
 
-function drawThresholdLine(
+// draws input level (left to right) against output level (bottom to top)
+// both axes go from -100 dB to 0 dB, the same scale as the gain graphs
+function drawTransferCurve(
   ctx: CanvasRenderingContext2D,
-  threshold: number,
+  curve: TransferCurve,
   width: number,
   height: number
 ) {
-  ctx.fillStyle = "black";
+  ctx.strokeStyle = "black";
+  ctx.lineWidth = 1;
 
-  // optionally, add some text
-  // i think it looks to busy though
-
-  //ctx.font = "8px Arial";
-  // ctx.fillText("Thresh", 0 + 2.5, -threshold);
-  ctx.fillRect(0, (-threshold * height) / 100, width, 1);
-}
-
-function drawKnee(
-  ctx: CanvasRenderingContext2D,
-  threshold: number,
-  knee: number,
-  width: number,
-  height: number
-) {
-  ctx.fillStyle = "rgba(0,0,0,0.2)";
-
-  // optionally, add some text
-  // i think it looks to busy though
-
-  //ctx.font = "8px Arial";
-  // ctx.fillText("Thresh", 0 + 2.5, -threshold);
-  ctx.fillRect(
-    0,
-    ((-threshold - knee) * height) / 100,
-    width,
-    (knee * 2 * height) / 100
-  );
+  ctx.beginPath();
+  for (let i = 0; i < curve.input_db.length; i++) {
+    const x = ((curve.input_db[i] + 100) * width) / 100;
+    const y = -(curve.output_db[i] * height) / 100;
+    if (i === 0) {
+      ctx.moveTo(x, y);
+    } else {
+      ctx.lineTo(x, y);
+    }
+  }
+  ctx.stroke();
 }
@@ -0,0 +1,67 @@
+import { useAmplitudeUpdate } from "@/hooks";
+import { gainToDb } from "@/lib/utils";
+import { useEffect, useRef } from "react";
+
+// shows the gain reduction of each band in multiband mode, from low to high
+// this is basically a GRMeter for every band, side by side
+export function BandGRMeter() {
+  const canvasRef = useRef<HTMLCanvasElement | null>(null);
+  // dimensions of canvas
+  const width = 40;
+  const height = 160;
+
+  // same normalization as GRMeter
+  const maxDB = 100;
+
+  const bands = useRef<number[]>([]);
+  const { bandReduced } = useAmplitudeUpdate();
+  bands.current = bandReduced.map((reduced) => Math.abs(gainToDb(reduced)));
+
+  useEffect(() => {
+    let animationRequest: number;
+    // TODO: deal with !
+    const canvas = canvasRef.current!;
+    const ctx = canvas.getContext("2d")!;
+
+    const draw = () => {
+      ctx.clearRect(0, 0, width, height);
+
+      ctx.fillStyle = "#b42770";
+
+      const numBands = bands.current.length;
+      const barWidth = width / Math.max(numBands, 1);
+      for (let i = 0; i < numBands; i++) {
+        const h = Math.min((bands.current[i] * height) / maxDB, maxDB);
+        // leave a 1px gap between bars
+        ctx.fillRect(i * barWidth, 0, barWidth - 1, h);
+      }
+
+      animationRequest = requestAnimationFrame(draw);
+    };
+    animationRequest = requestAnimationFrame(draw);
+
+    // cleanup
+    return () => {
+      cancelAnimationFrame(animationRequest);
+    };
+  }, []);
+
+  // nothing to show unless multiband is on
+  // (the canvas still has to exist, otherwise there's nothing to draw on once it's turned on)
+  const hidden = bandReduced.length === 0 ? "hidden" : "";
+
+  return (
+    <div className={`text-center text-xs ${hidden}`}>
+      <p>BANDS</p>
+      <canvas
+        ref={canvasRef}
+        width={width}
+        height={height}
+        className="border-2 border-gray-800"
+      ></canvas>
+      <p className="">
+        {bands.current.map((gr) => Math.round(gr)).join(" ")}
+      </p>
+    </div>
+  );
+}
@@ -0,0 +1,48 @@
+import clsx from "clsx";
+import { useEffect, useState } from "react";
+import { Parameter } from "@/bindings/Parameter";
+import { ParameterType, sendToPlugin } from "@/lib";
+
+// a toggle for the boolean parameters, like the "listen" ones that send the filtered key to the output
+export function ToggleButton(props: {
+  label: string;
+  parameter: ParameterType;
+}) {
+  const { label, parameter } = props;
+  const [enabled, setEnabled] = useState(false);
+
+  // listen for DAW parameter events and update state, just like the knobs
+  useEffect(() => {
+    const handlePluginMessage = (event: any) => {
+      const message: Parameter = event.detail;
+      if (parameter in message) {
+        setEnabled(Boolean(Object.values(message)[0]));
+      }
+    };
+
+    window.addEventListener("pluginMessage", handlePluginMessage);
+    return () => {
+      window.removeEventListener("pluginMessage", handlePluginMessage);
+    };
+  }, []);
+
+  function toggle() {
+    const value = !enabled;
+    setEnabled(value);
+    sendToPlugin({
+      ParameterUpdate: { [parameter]: value } as Parameter,
+    });
+  }
+
+  return (
+    <button
+      className={clsx(
+        "text-xs px-2 py-1 rounded border border-[#180619]",
+        enabled && "bg-[#180619] text-white"
+      )}
+      onClick={toggle}
+    >
+      {label}
+    </button>
+  );
+}
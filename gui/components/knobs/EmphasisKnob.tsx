@@ -0,0 +1,30 @@
+import { KnobBase } from "../KnobBase";
+import { NormalisableRange } from "@/lib/utils";
+import { ParameterType } from "@/lib";
+
+// the transient shaper's attack and sustain emphasis, which can go either way from 0 dB
+export function EmphasisKnob(props: {
+  label: string;
+  parameter: ParameterType;
+  value: number;
+  setValue: any;
+}) {
+  const { label, parameter, value, setValue } = props;
+  return (
+    <KnobBase
+      label={label}
+      valueRaw={value}
+      setRawValue={setValue}
+      valueMin={-12}
+      valueMax={12}
+      valueDefault={0}
+      range={new NormalisableRange(-12, 12, 0)}
+      valueRawRoundFn={(valueRaw) => Number(valueRaw.toFixed(2))}
+      valueRawDisplayFn={(valueRaw) => `${Number(valueRaw.toFixed(1))} dB`}
+      size={96}
+      stepFn={(valueRaw: number): number => 0}
+      stepLargerFn={(valueRaw: number): number => 0}
+      parameter={parameter}
+    />
+  );
+}
@@ -0,0 +1,23 @@
+import { KnobBase } from "../KnobBase";
+import { NormalisableRange } from "@/lib/utils";
+
+export function RangeKnob(props: { value: number; setValue: any }) {
+  const { value, setValue } = props;
+  return (
+    <KnobBase
+      label="RANGE"
+      valueRaw={value}
+      setRawValue={setValue}
+      valueMin={0}
+      valueMax={60}
+      valueDefault={60}
+      range={new NormalisableRange(0, 60, 20)}
+      valueRawRoundFn={(valueRaw) => Number(valueRaw.toFixed(2))}
+      valueRawDisplayFn={(valueRaw) => `${Number(valueRaw.toFixed(2))} dB`}
+      size={96}
+      stepFn={(valueRaw: number): number => 0}
+      stepLargerFn={(valueRaw: number): number => 0}
+      parameter="Range"
+    />
+  );
+}
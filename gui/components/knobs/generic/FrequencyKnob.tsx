@@ -0,0 +1,42 @@
+import { Dispatch, SetStateAction } from "react";
+import { KnobBase } from "../../KnobBase";
+import { NormalisableRange } from "@/lib/utils";
+import { ParameterType } from "@/lib";
+
+export function FrequencyKnob(props: {
+  label: string;
+  maxValue: number;
+  minValue: number;
+  defaultValue: number;
+  range: NormalisableRange;
+  parameter: ParameterType;
+  value: number;
+  setValue: Dispatch<SetStateAction<number>>;
+}) {
+  const { label, maxValue, minValue, defaultValue, range, value, setValue } =
+    props;
+
+  const displayFn = (valueRaw: number) => {
+    if (valueRaw >= 1000) {
+      return `${Number((valueRaw / 1000).toFixed(2))} kHz`;
+    }
+    return `${Math.round(valueRaw)} Hz`;
+  };
+  return (
+    <KnobBase
+      label={label}
+      valueRaw={value}
+      setRawValue={setValue}
+      valueMin={minValue}
+      valueMax={maxValue}
+      valueDefault={defaultValue}
+      range={range}
+      valueRawRoundFn={(valueRaw) => Math.round(valueRaw)}
+      valueRawDisplayFn={displayFn}
+      size={96}
+      stepFn={(valueRaw: number): number => 0}
+      stepLargerFn={(valueRaw: number): number => 0}
+      parameter={props.parameter}
+    />
+  );
+}
@@ -1,4 +1,5 @@
 import { Amplitude } from "@/bindings/Amplitude";
+import { TransferCurve } from "@/bindings/TransferCurve";
 import { MutableRefObject, useEffect, useRef, useState } from "react";
 
 // TODO:
@@ -6,6 +7,9 @@ import { MutableRefObject, useEffect, useRef, useState } from "react";
 function isAmplitudeMessage(message: any): message is Amplitude {
   return (message as Amplitude).pre_amplitude !== undefined;
 }
+function isTransferCurveMessage(message: any): message is TransferCurve {
+  return (message as TransferCurve).input_db !== undefined;
+}
 /*
 
 
@@ -42,7 +46,12 @@ export function useDecayFactor(decayMs: number) {
 }
 
 export function useAmplitudeUpdate() {
-  const [amplitudes, setAmplitudes] = useState({ pre: 0, post: 0, reduced: 0 });
+  const [amplitudes, setAmplitudes] = useState({
+    pre: 0,
+    post: 0,
+    reduced: 0,
+    bandReduced: [] as number[],
+  });
   // update state based on incoming messages
   useEffect(() => {
     // NOTE:
@@ -57,6 +66,7 @@ export function useAmplitudeUpdate() {
         pre: message.pre_amplitude,
         post: message.post_amplitude,
         reduced: message.amt_reduced,
+        bandReduced: message.band_reduced,
       });
     };
 
@@ -68,6 +78,29 @@ export function useAmplitudeUpdate() {
   return amplitudes;
 }
 
+// Custom hook which returns the latest static curve from the plugin.
+// the plugin calculates this with the same gain computer the DSP uses, so we never have to approximate it here
+export function useTransferCurve() {
+  const [curve, setCurve] = useState<TransferCurve>({
+    input_db: [],
+    output_db: [],
+  });
+  useEffect(() => {
+    const handlePluginMessage = (event: any) => {
+      const message = event.detail;
+      if (!isTransferCurveMessage(message)) {
+        return;
+      }
+      setCurve(message);
+    };
+    window.addEventListener("pluginMessage", handlePluginMessage);
+    return () => {
+      window.removeEventListener("pluginMessage", handlePluginMessage);
+    };
+  }, []);
+  return curve;
+}
+
 // Custom hook which keeps track of front-end sample rate.
 export function useSampleRate() {
   const initTime = useRef(Date.now());
@@ -1,112 +1,295 @@
-/*
-use dsp::{Compressor, LevelDetectionType};
+use std::{
+    error::Error,
+    fs::File,
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use clap::{Parser, ValueEnum};
+use comprs::{
+    dsp::transfer_curve,
+    preset::{Overrides, Preset},
+    processor::Processor,
+};
 use nih_plug::util::db_to_gain;
-use plotters::prelude::*;
-// TODO: make this a timestamp or something idk
-const OUT_FILE_NAME: &str = "plots/0.png";
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let len = 44_100;
-    //let mut rng = rand::thread_rng();
-    let mut data: Vec<f32> = vec![0.0; len];
-    for (index, value) in data.iter_mut().enumerate() {
-        let q = len / 4;
-        let factor = {
-            if index >= (q * 3) {
-                -12.0
-            } else if index >= (q * 2) {
-                0.0
-            } else if index >= (q) {
-                -9.0
-            } else {
-                -12.0
-            }
+use plotters::{coord::Shift, prelude::*};
+
+/// The test signals we can run through the compressor.
+#[derive(Clone, Copy, ValueEnum)]
+enum Signal {
+    /// A sine that steps between a few different levels
+    Step,
+    /// Short, loud bursts over a quiet sine
+    Burst,
+    /// A sine that sweeps from 20 Hz to 20 kHz at a constant level
+    Sweep,
+}
+
+#[derive(Parser)]
+#[command(about = "Plots how the COMPRS compressor reacts to some test signals")]
+struct Args {
+    /// The test signal to use
+    #[arg(long, value_enum, default_value = "step")]
+    signal: Signal,
+    /// Plot the static transfer curve (input level against output level) instead of running a test signal
+    #[arg(long)]
+    transfer_curve: bool,
+    /// Also write the transfer curve to this CSV file
+    #[arg(long)]
+    csv: Option<PathBuf>,
+    /// Where to save the plot. Use a `.svg` extension for an SVG, anything else is a PNG.
+    /// Defaults to `plots/<signal>.png`, or `plots/transfer_curve.png` with `--transfer-curve`
+    #[arg(long)]
+    output: Option<PathBuf>,
+    /// A JSON preset, in the same format as the `render` presets (see `comprs::preset`)
+    #[arg(long)]
+    preset: Option<PathBuf>,
+    /// Threshold in dB
+    #[arg(long, allow_negative_numbers = true)]
+    threshold: Option<f32>,
+    /// Compression ratio, e.g. 4 for 4:1
+    #[arg(long)]
+    ratio: Option<f32>,
+    /// Knee width in dB
+    #[arg(long)]
+    knee: Option<f32>,
+    /// Attack time in seconds
+    #[arg(long)]
+    attack: Option<f32>,
+    /// Release time in seconds
+    #[arg(long)]
+    release: Option<f32>,
+    /// Length of the test signal in seconds
+    #[arg(long, default_value_t = 1.0)]
+    seconds: f32,
+    #[arg(long, default_value_t = 44_100.0)]
+    sample_rate: f32,
+}
+
+/// Everything we collect while running the compressor, one entry per sample.
+/// The output is latency-compensated, so it lines up with the input.
+struct Results {
+    input: Vec<f32>,
+    output: Vec<f32>,
+    envelope: Vec<f32>,
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args = Args::parse();
+
+    let overrides = Overrides {
+        threshold: args.threshold,
+        ratio: args.ratio,
+        knee_width: args.knee,
+        attack_time: args.attack,
+        release_time: args.release,
+        ..Default::default()
+    };
+    let preset = Preset::load(args.preset.as_deref(), &overrides)?;
+    let settings = &preset.settings;
+
+    // the transfer curve covers the same range as the plugin's GUI
+    let curve: Vec<(f32, f32)> =
+        transfer_curve(settings.curve, settings.character, -100.0, 0.0, 1001).collect();
+    if let Some(path) = &args.csv {
+        write_curve_csv(path, &curve)?;
+        println!("Transfer curve has been saved to {}", path.display());
+    }
+
+    let output = args.output.clone().unwrap_or_else(|| {
+        let name = match args.signal {
+            _ if args.transfer_curve => "transfer_curve",
+            Signal::Step => "step",
+            Signal::Burst => "burst",
+            Signal::Sweep => "sweep",
         };
-        *value = (index as f32 * 0.1).sin() * db_to_gain(factor);
+        PathBuf::from(format!("plots/{name}.png"))
+    });
+    if let Some(parent) = output.parent() {
+        std::fs::create_dir_all(parent)?;
     }
-    let threshold = -10.0;
-    let ratio = 100.0;
-    let knee = 5.0;
-    let attack_time = 0.005;
-    let release_time = 0.05;
-    let mut comp = Compressor::new(
-        attack_time,
-        release_time,
-        threshold,
-        ratio,
-        knee,
-        LevelDetectionType::Rms,
-    );
-    let compressed_data: Vec<((f32, f32), f32)> = data
-        .iter()
-        .map(|sample| {
-            let result = comp.process(*sample);
-            (result, comp.get_average_gain())
+
+    let size = (2000, 2000);
+    let svg = output
+        .extension()
+        .is_some_and(|extension| extension == "svg");
+    if args.transfer_curve {
+        if svg {
+            draw_curve(SVGBackend::new(&output, size).into_drawing_area(), &curve)?;
+        } else {
+            draw_curve(
+                BitMapBackend::new(&output, size).into_drawing_area(),
+                &curve,
+            )?;
+        }
+    } else {
+        let data = generate_signal(args.signal, args.sample_rate, args.seconds);
+        let results = run_processor(&data, &preset, args.sample_rate);
+        if svg {
+            draw(
+                SVGBackend::new(&output, size).into_drawing_area(),
+                &results,
+                preset.settings.curve.threshold,
+            )?;
+        } else {
+            draw(
+                BitMapBackend::new(&output, size).into_drawing_area(),
+                &results,
+                preset.settings.curve.threshold,
+            )?;
+        }
+    }
+    println!("Result has been saved to {}", output.display());
+    Ok(())
+}
+
+/// Generates one of the test signals.
+fn generate_signal(signal: Signal, sample_rate: f32, seconds: f32) -> Vec<f32> {
+    let len = (sample_rate * seconds) as usize;
+    (0..len)
+        .map(|index| {
+            let t = index as f32 / sample_rate;
+            match signal {
+                Signal::Step => {
+                    let q = len / 4;
+                    let factor = {
+                        if index >= (q * 3) {
+                            -12.0
+                        } else if index >= (q * 2) {
+                            0.0
+                        } else if index >= (q) {
+                            -9.0
+                        } else {
+                            -12.0
+                        }
+                    };
+                    (index as f32 * 0.1).sin() * db_to_gain(factor)
+                }
+                Signal::Burst => {
+                    // 50 ms bursts, 4 times a second
+                    let factor = if t % 0.25 < 0.05 { 0.0 } else { -30.0 };
+                    (index as f32 * 0.1).sin() * db_to_gain(factor)
+                }
+                Signal::Sweep => {
+                    // exponential sweep, so every octave gets the same amount of time
+                    let (start, end) = (20.0f32, 20_000.0f32);
+                    let k = (end / start).ln() / seconds;
+                    let phase = std::f32::consts::TAU * start * ((k * t).exp() - 1.0) / k;
+                    phase.sin() * db_to_gain(-6.0)
+                }
+            }
         })
-        .collect();
-    let (compression_results, envelopes): ((Vec<f32>, Vec<f32>), Vec<f32>) =
-        compressed_data.into_iter().unzip();
+        .collect()
+}
 
-    let root = BitMapBackend::new(OUT_FILE_NAME, (2000, 2000)).into_drawing_area();
+/// Runs a mono signal through a `Processor` set up the same way as in the plugin.
+fn run_processor(data: &[f32], preset: &Preset, sample_rate: f32) -> Results {
+    let mut processor = Processor::new(sample_rate);
+    processor.set_oversampling(preset.oversampling.factor());
+    processor.set_rms_buffer_size(preset.rms_buffer_size);
+    let settings = preset.processor_settings(1);
 
+    // like in `render`, we run for a little longer (on silence) and drop the start of the output
+    let latency = processor.latency(preset.settings.lookahead, preset.limiter_enabled) as usize;
+    let mut results = Results {
+        input: data.to_vec(),
+        output: Vec::with_capacity(data.len()),
+        envelope: Vec::with_capacity(data.len()),
+    };
+    for index in 0..data.len() + latency {
+        let mut frame = [data.get(index).copied().unwrap_or(0.0)];
+        processor.process(&mut frame, None, &settings);
+        if index >= latency {
+            results.output.push(frame[0]);
+        }
+        // the envelope follows the *input*, which isn't delayed by the latency
+        if index < data.len() {
+            results
+                .envelope
+                .push(processor.compressors()[0].average_gain());
+        }
+    }
+    results
+}
+
+/// Draws the input (red), output (green), envelope (blue) and threshold (black).
+fn draw<DB: DrawingBackend>(
+    root: DrawingArea<DB, Shift>,
+    results: &Results,
+    threshold: f32,
+) -> Result<(), Box<dyn Error>>
+where
+    DB::ErrorType: 'static,
+{
     root.fill(&WHITE)?;
 
+    let len = results.input.len();
     let mut chart = ChartBuilder::on(&root)
         .set_label_area_size(LabelAreaPosition::Left, 60)
         .set_label_area_size(LabelAreaPosition::Bottom, 60)
         .caption("Compressor Gain", ("JetBrains Mono", 40))
-        .build_cartesian_2d(0.0..(data.len() as f32 - 1.0), -1.0..1.0f32)?;
+        .build_cartesian_2d(0.0..(len as f32 - 1.0), -1.0..1.0f32)?;
 
     chart.configure_mesh().disable_x_mesh().draw()?;
 
-    chart.draw_series(LineSeries::new(
-        data.iter().enumerate().map(|(x, y)| (x as f32, *y)),
-        RED,
-    ))?;
-
-    chart.draw_series(LineSeries::new(
-        compression_results
-            .0
-            .iter()
+    let series = |data: &[f32]| -> Vec<(f32, f32)> {
+        data.iter()
             .enumerate()
-            .map(|(x, y)| (x as f32, *y)),
-        GREEN,
-    ))?;
-    chart.draw_series(LineSeries::new(
-        envelopes.iter().enumerate().map(|(x, y)| (x as f32, *y)),
-        BLUE,
-    ))?;
-    chart.draw_series(LineSeries::new(
-        vec![db_to_gain(threshold); data.len()]
-            .iter()
-            .enumerate()
-            .map(|(x, y)| (x as f32, *y)),
-        BLACK,
-    ))?;
+            .map(|(x, y)| (x as f32, *y))
+            .collect()
+    };
 
-    /*
+    chart.draw_series(LineSeries::new(series(&results.input), RED))?;
+    chart.draw_series(LineSeries::new(series(&results.output), GREEN))?;
+    chart.draw_series(LineSeries::new(series(&results.envelope), BLUE))?;
     chart.draw_series(LineSeries::new(
-        compression_results
-            .1
-            .iter()
-            .enumerate()
-            .map(|(x, y)| (x as f32, *y)),
+        series(&vec![db_to_gain(threshold); len]),
         BLACK,
     ))?;
 
+    // To avoid the IO failure being ignored silently, we manually call the present function
+    root.present()?;
+    Ok(())
+}
 
+/// Draws the static transfer curve (black), with the 1:1 line (grey) for reference.
+fn draw_curve<DB: DrawingBackend>(
+    root: DrawingArea<DB, Shift>,
+    curve: &[(f32, f32)],
+) -> Result<(), Box<dyn Error>>
+where
+    DB::ErrorType: 'static,
+{
+    root.fill(&WHITE)?;
+
+    let mut chart = ChartBuilder::on(&root)
+        .set_label_area_size(LabelAreaPosition::Left, 60)
+        .set_label_area_size(LabelAreaPosition::Bottom, 60)
+        .caption("Transfer Curve", ("JetBrains Mono", 40))
+        .build_cartesian_2d(-100.0..0.0f32, -100.0..0.0f32)?;
+
+    chart
+        .configure_mesh()
+        .x_desc("Input (dB)")
+        .y_desc("Output (dB)")
+        .draw()?;
 
     chart.draw_series(LineSeries::new(
-        vec![-threshold; 44100]
-            .iter()
-            .enumerate()
-            .map(|(x, y)| (x as f32, *y)),
-        BLACK,
+        curve.iter().map(|(input_db, _)| (*input_db, *input_db)),
+        RGBColor(180, 180, 180),
     ))?;
-    */
-    // To avoid the IO failure being ignored silently, we manually call the present function
-    root.present().expect("Unable to write result to file, please make sure the proper output dir exists under current dir");
-    println!("Result has been saved to {}", OUT_FILE_NAME);
+    chart.draw_series(LineSeries::new(curve.iter().copied(), BLACK))?;
+
+    root.present()?;
     Ok(())
 }
-*/
-fn main() {}
+
+/// Writes the transfer curve to a CSV file, one point per row.
+fn write_curve_csv(path: &Path, curve: &[(f32, f32)]) -> std::io::Result<()> {
+    let mut file = BufWriter::new(File::create(path)?);
+    writeln!(file, "input_db,output_db")?;
+    for (input_db, output_db) in curve {
+        writeln!(file, "{input_db},{output_db}")?;
+    }
+    file.flush()
+}
@@ -0,0 +1,290 @@
+//! Renders WAV files through the same `Processor` that the plugin uses, without needing a DAW.
+//!
+//! Example:
+//! ```sh
+//! cargo run --release -p render -- drums.wav drums_compressed.wav --threshold -20 --ratio 8 --gain-reduction-csv gr.csv
+//! ```
+//! Settings can also come from a JSON preset (`--preset`, see `comprs::preset` for the format), in which case any flags override the preset.
+
+use std::{
+    error::Error,
+    fs::File,
+    io::{BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use clap::Parser;
+use comprs::{
+    dsp::{Character, EnvelopeType, LevelDetectionType, Topology},
+    preset::{Overrides, Preset},
+    processor::Processor,
+};
+use hound::{SampleFormat, WavReader, WavSpec, WavWriter};
+use nih_plug::util::gain_to_db;
+use serde::de::DeserializeOwned;
+
+#[derive(Parser)]
+#[command(about = "Renders WAV files through the COMPRS compressor")]
+struct Args {
+    /// The WAV file to process
+    input: PathBuf,
+    /// Where to write the processed WAV file. This uses the same format as the input
+    output: PathBuf,
+    /// A WAV file to use as the external sidechain (the plugin's aux input). It has to have the same sample rate as the input
+    #[arg(long)]
+    sidechain: Option<PathBuf>,
+    /// A JSON preset to load settings from. Flags override anything in the preset
+    #[arg(long)]
+    preset: Option<PathBuf>,
+
+    /// Threshold in dB
+    #[arg(long, allow_negative_numbers = true)]
+    threshold: Option<f32>,
+    /// Compression ratio, e.g. 4 for 4:1
+    #[arg(long)]
+    ratio: Option<f32>,
+    /// Knee width in dB
+    #[arg(long)]
+    knee: Option<f32>,
+    /// Attack time in seconds
+    #[arg(long)]
+    attack: Option<f32>,
+    /// Release time in seconds
+    #[arg(long)]
+    release: Option<f32>,
+    /// Hold time in seconds
+    #[arg(long)]
+    hold: Option<f32>,
+    /// How much the release adapts to the material, from 0.0 (fixed) to 1.0
+    #[arg(long)]
+    auto_release: Option<f32>,
+    /// Lookahead in seconds. The output is latency-compensated, so it still lines up with the input
+    #[arg(long)]
+    lookahead: Option<f32>,
+    /// RMS window length in seconds
+    #[arg(long)]
+    rms_size: Option<f32>,
+    /// Blend between the independent (0.0) and shared (1.0) levels
+    #[arg(long)]
+    rms_mix: Option<f32>,
+    /// Level detector: peak, rms or true-peak
+    #[arg(long, value_parser = parse_setting::<LevelDetectionType>)]
+    detector: Option<LevelDetectionType>,
+    /// Topology: feed-forward or feedback
+    #[arg(long, value_parser = parse_setting::<Topology>)]
+    topology: Option<Topology>,
+    /// Envelope: level, smooth-branching or smooth-decoupled
+    #[arg(long, value_parser = parse_setting::<EnvelopeType>)]
+    envelope: Option<EnvelopeType>,
+    /// Character: vca, fet, opto or vari-mu
+    #[arg(long, value_parser = parse_setting::<Character>)]
+    character: Option<Character>,
+
+    /// Write the detector envelope (in dB) for every sample to this CSV file. This only follows the full-band compressor
+    #[arg(long)]
+    envelope_csv: Option<PathBuf>,
+    /// Write the gain reduction (in dB) for every sample to this CSV file, lined up with the output
+    #[arg(long)]
+    gain_reduction_csv: Option<PathBuf>,
+}
+
+/// Parses a setting from the command line using the same names as the JSON presets.
+fn parse_setting<T: DeserializeOwned>(value: &str) -> Result<T, String> {
+    serde_json::from_value(serde_json::Value::String(value.to_owned())).map_err(|e| e.to_string())
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args = Args::parse();
+    let preset = load_preset(&args)?;
+
+    let mut reader = WavReader::open(&args.input)?;
+    let spec = reader.spec();
+    let channels = spec.channels as usize;
+    let sample_rate = spec.sample_rate as f32;
+    // the plugin only has mono and stereo layouts
+    if channels > 2 {
+        return Err(format!("{channels} channels aren't supported, only mono and stereo").into());
+    }
+    let input = read_samples(&mut reader)?;
+
+    let sidechain = match &args.sidechain {
+        Some(path) => {
+            let mut reader = WavReader::open(path)?;
+            if reader.spec().sample_rate != spec.sample_rate {
+                return Err("the sidechain has to have the same sample rate as the input".into());
+            }
+            let aux_channels = (reader.spec().channels as usize).min(channels);
+            Some((
+                read_samples(&mut reader)?,
+                reader.spec().channels as usize,
+                aux_channels,
+            ))
+        }
+        None => None,
+    };
+
+    // set everything up the same way the plugin does in `initialize()`
+    let mut processor = Processor::new(sample_rate);
+    processor.set_oversampling(preset.oversampling.factor());
+    processor.set_rms_buffer_size(preset.rms_buffer_size);
+    let settings = preset.processor_settings(channels);
+
+    // a host would compensate for the latency, so we do too.
+    // we run the processor for a little longer (on silence) and then drop the start of the output
+    let latency = processor.latency(preset.settings.lookahead, preset.limiter_enabled) as usize;
+    let total_frames = input.len() / channels + latency;
+
+    let mut output = Vec::with_capacity(total_frames * channels);
+    let mut envelopes = Vec::with_capacity(input.len());
+    let mut gain_reductions = Vec::with_capacity(total_frames * channels);
+
+    for frame_index in 0..total_frames {
+        let mut frame = [0.0; 2];
+        for (channel, sample) in frame.iter_mut().enumerate().take(channels) {
+            *sample = input
+                .get(frame_index * channels + channel)
+                .copied()
+                .unwrap_or(0.0);
+        }
+        // like in the plugin, a channel that's missing from the sidechain falls back to the main input
+        let aux_frame = sidechain
+            .as_ref()
+            .map(|(samples, file_channels, aux_channels)| {
+                let mut aux_frame = [0.0; 2];
+                for (channel, sample) in aux_frame.iter_mut().enumerate().take(*aux_channels) {
+                    *sample = samples
+                        .get(frame_index * file_channels + channel)
+                        .copied()
+                        .unwrap_or(0.0);
+                }
+                (aux_frame, *aux_channels)
+            });
+
+        let meters = processor.process(
+            &mut frame[..channels],
+            aux_frame
+                .as_ref()
+                .map(|(aux_frame, aux_channels)| &aux_frame[..*aux_channels]),
+            &settings,
+        );
+
+        output.extend_from_slice(&frame[..channels]);
+        gain_reductions.extend(
+            meters.amount_reduced[..channels]
+                .iter()
+                .map(|gr| gain_to_db(*gr)),
+        );
+        // the envelope follows the *input*, which isn't delayed by the latency
+        if frame_index * channels < input.len() {
+            envelopes.extend(
+                processor.compressors()[..channels]
+                    .iter()
+                    .map(|compressor| gain_to_db(compressor.average_gain())),
+            );
+        }
+    }
+
+    let skipped = latency * channels;
+    write_samples(&args.output, spec, &output[skipped..])?;
+    println!("Result has been saved to {}", args.output.display());
+
+    if let Some(path) = &args.envelope_csv {
+        write_csv(path, sample_rate, channels, &envelopes)?;
+        println!("Envelope has been saved to {}", path.display());
+    }
+    if let Some(path) = &args.gain_reduction_csv {
+        // the meters come out before the downsampler and the limiter, so they're only behind by the lookahead and the upsampler.
+        // skipping just that much lines them up with the output file, with the gain reduction starting ahead of a transient
+        let meter_latency = processor.meter_latency(preset.settings.lookahead) as usize;
+        let skipped = meter_latency * channels;
+        write_csv(
+            path,
+            sample_rate,
+            channels,
+            &gain_reductions[skipped..skipped + input.len()],
+        )?;
+        println!("Gain reduction has been saved to {}", path.display());
+    }
+
+    Ok(())
+}
+
+/// Loads the preset (if there is one) and applies any overrides from the command line.
+fn load_preset(args: &Args) -> Result<Preset, Box<dyn Error>> {
+    let overrides = Overrides {
+        threshold: args.threshold,
+        ratio: args.ratio,
+        knee_width: args.knee,
+        attack_time: args.attack,
+        release_time: args.release,
+        hold_time: args.hold,
+        auto_release: args.auto_release,
+        lookahead: args.lookahead,
+        rms_buffer_size: args.rms_size,
+        rms_mix: args.rms_mix,
+        level_detection_type: args.detector,
+        topology: args.topology,
+        envelope_type: args.envelope,
+        character: args.character,
+    };
+    Preset::load(args.preset.as_deref(), &overrides)
+}
+
+/// Reads every sample (interleaved) from a WAV file, scaled so that full scale is `1.0`.
+fn read_samples(reader: &mut WavReader<BufReader<File>>) -> Result<Vec<f32>, hound::Error> {
+    let spec = reader.spec();
+    match spec.sample_format {
+        SampleFormat::Float => reader.samples::<f32>().collect(),
+        SampleFormat::Int => {
+            let scale = (1i64 << (spec.bits_per_sample - 1)) as f32;
+            reader
+                .samples::<i32>()
+                .map(|sample| sample.map(|sample| sample as f32 / scale))
+                .collect()
+        }
+    }
+}
+
+/// Writes interleaved samples to a WAV file, converting back to integers if needed.
+fn write_samples(path: &Path, spec: WavSpec, samples: &[f32]) -> Result<(), hound::Error> {
+    let mut writer = WavWriter::create(path, spec)?;
+    match spec.sample_format {
+        SampleFormat::Float => {
+            for sample in samples {
+                writer.write_sample(*sample)?;
+            }
+        }
+        SampleFormat::Int => {
+            let scale = (1i64 << (spec.bits_per_sample - 1)) as f32;
+            for sample in samples {
+                let sample = (sample * scale).round().clamp(-scale, scale - 1.0);
+                writer.write_sample(sample as i32)?;
+            }
+        }
+    }
+    writer.finalize()
+}
+
+/// Writes interleaved per-sample values to a CSV file, with one column per channel.
+fn write_csv(
+    path: &Path,
+    sample_rate: f32,
+    channels: usize,
+    values: &[f32],
+) -> std::io::Result<()> {
+    let mut file = BufWriter::new(File::create(path)?);
+
+    let header: Vec<String> = (1..=channels).map(|c| format!("channel_{c}_db")).collect();
+    writeln!(file, "sample,seconds,{}", header.join(","))?;
+
+    for (index, frame) in values.chunks(channels).enumerate() {
+        let row: Vec<String> = frame.iter().map(|value| value.to_string()).collect();
+        writeln!(
+            file,
+            "{index},{},{}",
+            index as f32 / sample_rate,
+            row.join(",")
+        )?;
+    }
+    file.flush()
+}
@@ -1,114 +1,689 @@
-use nih_plug::util::{db_to_gain_fast, gain_to_db_fast};
-use std::collections::VecDeque;
+use nih_plug::{
+    prelude::Enum,
+    util::{db_to_gain_fast, gain_to_db_fast},
+};
 
-use crate::params::CompressorParams;
+use serde::{Deserialize, Serialize};
+
+use crate::params::{
+    DEFAULT_ATTACK_TIME, DEFAULT_DE_ESSER_FREQUENCY, DEFAULT_EXPANSION_RANGE, DEFAULT_KNEE,
+    DEFAULT_RANGE, DEFAULT_RATIO, DEFAULT_RELEASE_TIME, DEFAULT_THRESHOLD, DEFAULT_UPWARD_RANGE,
+    MAX_RANGE,
+};
 // https://www.musicdsp.org/en/latest/Effects/169-compressor.html (not the best source)
 // recommended:
 // https://www.eecs.qmul.ac.uk/~josh/documents/2012/GiannoulisMassbergReiss-dynamicrangecompression-JAES2012.pdf
 
+/// Common interface for anything that can measure *the level* of a signal.
+pub trait LevelDetector {
+    /// Feeds a new sample into the detector and returns the current level *in linear space*.
+    fn calculate_level(&mut self, input: f32) -> f32;
+}
+
+/// Struct to represent a simple delay line with a fixed maximum length. This is what we use for lookahead.
+/// The memory is only allocated in `new()`, so changing the delay while processing is allocation-free.
+pub struct DelayLine {
+    buffer: Vec<f32>,
+    write_index: usize,
+}
+impl Default for DelayLine {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+impl DelayLine {
+    /// Construct a new `DelayLine` which can delay by up to `max_delay` samples.
+    pub fn new(max_delay: usize) -> Self {
+        Self {
+            buffer: vec![0.0; max_delay + 1],
+            write_index: 0,
+        }
+    }
+    /// The maximum delay, in samples.
+    pub fn max_delay(&self) -> usize {
+        self.buffer.len() - 1
+    }
+    /// Returns the sample that was written `delay` samples ago, without writing anything.
+    /// A delay of `0` is the most recently written sample.
+    pub fn get(&self, delay: usize) -> f32 {
+        let length = self.buffer.len();
+        let delay = delay.min(length - 1);
+        self.buffer[(self.write_index + length - 1 - delay) % length]
+    }
+    /// Writes a new sample into the delay line, and returns the sample from `delay` samples ago.
+    /// The delay is clamped to the maximum length of the delay line.
+    pub fn process(&mut self, input: f32, delay: usize) -> f32 {
+        let length = self.buffer.len();
+        self.buffer[self.write_index] = input;
+
+        let delay = delay.min(length - 1);
+        let read_index = (self.write_index + length - delay) % length;
+        self.write_index = (self.write_index + 1) % length;
+
+        self.buffer[read_index]
+    }
+    /// Clears the delay line to silence. This doesn't allocate, so it's fine to call from `process()`.
+    pub fn reset(&mut self) {
+        self.reset_to(0.0);
+    }
+    /// Fills the whole delay line with `value`, as if that's all that had ever been written. This doesn't allocate either.
+    pub fn reset_to(&mut self, value: f32) {
+        self.buffer.fill(value);
+        self.write_index = 0;
+    }
+}
+
+/// Scale used to convert squared samples to fixed point for the RMS running sum.
+/// With 64 fractional bits, only squares below ~1e-19 (roughly -190 dB) round down to zero,
+/// and a `u128` still has more than enough room for a full window of very hot samples.
+const RMS_FIXED_POINT_SCALE: f64 = (1u128 << 64) as f64;
+
+/// Squares a sample and converts it to fixed point.
+fn squared_fixed_point(sample: f32) -> u128 {
+    let sample = sample as f64;
+    // NaN becomes 0 and infinity saturates, so this can't fail
+    (sample * sample * RMS_FIXED_POINT_SCALE) as u128
+}
+
 /// Struct to represent an RMS level detector. Uses a running sum and circular buffer.
+///
+/// The circular buffer is allocated once with a maximum length, and the RMS window is just the most recent `length` samples.
+/// This means the window can be resized while processing without allocating.
+///
+/// The running sum is kept in fixed point instead of floating point.
+/// Integer addition and subtraction are exact, so every square that gets added is removed *exactly* when it leaves the window.
+/// With floats, tiny rounding errors would pile up over long sessions (and the sum could even go negative, which gives NaN).
+/// With fixed point, the sum is always exactly the sum of the window, no matter how long we run for.
 pub struct RmsLevelDetector {
-    squared_sum: f32,
-    pub buffer: VecDeque<f32>,
+    /// Sum of the squares of the window, in fixed point (see `RMS_FIXED_POINT_SCALE`).
+    squared_sum: u128,
+    /// History of input samples. This always holds as many samples as it can, not just the current window.
+    history: DelayLine,
+    /// The number of samples in the RMS window.
+    length: usize,
 }
 impl Default for RmsLevelDetector {
     fn default() -> Self {
+        Self::new(1)
+    }
+}
+impl RmsLevelDetector {
+    /// Construct a new `RmsLevelDetector` whose window can be up to `max_length` samples long.
+    /// The window starts out at the maximum length.
+    pub fn new(max_length: usize) -> Self {
+        let max_length = max_length.max(1);
         Self {
-            squared_sum: 0.0,
-            buffer: VecDeque::new(),
+            squared_sum: 0,
+            history: DelayLine::new(max_length),
+            length: max_length,
         }
     }
+    /// Changes the number of samples in the RMS window. This never allocates, so it's safe to call while processing.
+    /// The new length is clamped between 1 and the maximum length passed to `new()`.
+    pub fn set_length(&mut self, length: usize) {
+        self.length = length.clamp(1, self.history.max_delay());
+        // because we keep the full history, growing the window brings back real samples instead of silence.
+        // either way, the running sum needs to match the new window
+        self.squared_sum = self.window_squared_sum();
+    }
+    /// Clears the history to silence, keeping the window length. This doesn't allocate.
+    pub fn reset(&mut self) {
+        self.history.reset();
+        self.squared_sum = 0;
+    }
+    /// Sums the squares of every sample in the current window.
+    fn window_squared_sum(&self) -> u128 {
+        (0..self.length).fold(0, |sum, delay| {
+            sum.wrapping_add(squared_fixed_point(self.history.get(delay)))
+        })
+    }
 }
-impl RmsLevelDetector {
-    pub fn calculate_rms(&mut self, input: f32) -> f32 {
-        let old_sample = self.buffer.pop_front().unwrap();
-        self.buffer.push_back(input);
-        self.squared_sum += input.powi(2);
-        self.squared_sum -= old_sample.powi(2);
-        //
-        // really weird workaround lol
-        // sometimes (for some reason), squared_sum can be negative, and square rooting a negative leads to NaN
-        //
-        // when the compressor's gain state is NaN, the compressor will just never compress
-        // TODO:
-        // panic on nan
-        self.squared_sum = self.squared_sum.max(0.0);
+impl LevelDetector for RmsLevelDetector {
+    fn calculate_level(&mut self, input: f32) -> f32 {
+        // the sample that falls out of the window is exactly `length` samples old
+        let old_sample = self.history.process(input, self.length);
+        // wrapping arithmetic is still exact (just modulo 2^128),
+        // so even if something absurd like infinity comes through, the sum is correct again once it leaves the window
+        self.squared_sum = self
+            .squared_sum
+            .wrapping_add(squared_fixed_point(input))
+            .wrapping_sub(squared_fixed_point(old_sample));
+
+        let mean_square = self.squared_sum as f64 / RMS_FIXED_POINT_SCALE / self.length as f64;
+        mean_square.sqrt() as f32
+    }
+}
+
+/// Struct to represent a (sample) peak level detector. This is just the absolute value of the input,
+/// the attack/release filter in the compressor takes care of the smoothing.
+#[derive(Default)]
+pub struct PeakLevelDetector;
+
+impl LevelDetector for PeakLevelDetector {
+    fn calculate_level(&mut self, input: f32) -> f32 {
+        input.abs()
+    }
+}
+
+/// Number of taps for each phase of the true-peak interpolation filter.
+const TRUE_PEAK_TAPS: usize = 12;
+/// Polyphase FIR coefficients for 4x oversampling, taken from ITU-R BS.1770-4 (Annex 2).
+/// Each row is one phase of the interpolation filter.
+// these are copied exactly from the spec, so we keep all of the digits
+#[allow(clippy::excessive_precision)]
+const TRUE_PEAK_COEFFICIENTS: [[f32; TRUE_PEAK_TAPS]; 4] = [
+    [
+        0.0017089843750,
+        0.0109863281250,
+        -0.0196533203125,
+        0.0332031250000,
+        -0.0594482421875,
+        0.1373291015625,
+        0.9721679687500,
+        -0.1022949218750,
+        0.0476074218750,
+        -0.0266113281250,
+        0.0148925781250,
+        -0.0083007812500,
+    ],
+    [
+        -0.0291748046875,
+        0.0292968750000,
+        -0.0517578125000,
+        0.0891113281250,
+        -0.1665039062500,
+        0.4650878906250,
+        0.7797851562500,
+        -0.2003173828125,
+        0.1015625000000,
+        -0.0582275390625,
+        0.0330810546875,
+        -0.0189208984375,
+    ],
+    [
+        -0.0189208984375,
+        0.0330810546875,
+        -0.0582275390625,
+        0.1015625000000,
+        -0.2003173828125,
+        0.7797851562500,
+        0.4650878906250,
+        -0.1665039062500,
+        0.0891113281250,
+        -0.0517578125000,
+        0.0292968750000,
+        -0.0291748046875,
+    ],
+    [
+        -0.0083007812500,
+        0.0148925781250,
+        -0.0266113281250,
+        0.0476074218750,
+        -0.1022949218750,
+        0.9721679687500,
+        0.1373291015625,
+        -0.0594482421875,
+        0.0332031250000,
+        -0.0196533203125,
+        0.0109863281250,
+        0.0017089843750,
+    ],
+];
+
+/// How many samples late the true-peak filter reports a peak.
+/// The biggest taps are at 5 and 6, so every oversampled value lies between the samples that came in 5 and 6 samples ago.
+const TRUE_PEAK_DELAY: usize = 6;
+
+/// Struct to represent a true-peak level detector, in the style of ITU-R BS.1770.
+/// The input is oversampled by 4x, and the largest (absolute) inter-sample value is used as the level.
+/// This catches peaks that happen *between* samples, which a regular peak detector would miss.
+pub struct TruePeakLevelDetector {
+    /// The most recent input samples, newest first.
+    history: [f32; TRUE_PEAK_TAPS],
+}
+impl Default for TruePeakLevelDetector {
+    fn default() -> Self {
+        Self {
+            history: [0.0; TRUE_PEAK_TAPS],
+        }
+    }
+}
+impl LevelDetector for TruePeakLevelDetector {
+    fn calculate_level(&mut self, input: f32) -> f32 {
+        // shift everything over by one to make room for the new sample
+        self.history.copy_within(0..TRUE_PEAK_TAPS - 1, 1);
+        self.history[0] = input;
 
-        (self.squared_sum / self.buffer.len() as f32).sqrt()
+        // each phase of the filter gives us one of the oversampled values
+        TRUE_PEAK_COEFFICIENTS
+            .iter()
+            .map(|phase| {
+                phase
+                    .iter()
+                    .zip(self.history.iter())
+                    .map(|(coefficient, sample)| coefficient * sample)
+                    .sum::<f32>()
+                    .abs()
+            })
+            .fold(0.0, f32::max)
     }
 }
+
 /// Variants represent the different types of level detection that the compressor may use to update its internal gain.
-/*
+#[derive(Enum, Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
 pub enum LevelDetectionType {
+    /// Use the (absolute) peak of the signal
+    #[id = "peak"]
+    Peak,
     /// Use RMS of the signal
+    #[id = "rms"]
+    #[name = "RMS"]
     Rms,
+    /// Use the oversampled, inter-sample peak of the signal
+    #[id = "truepeak"]
+    #[name = "True Peak"]
+    TruePeak,
+}
+
+/// Variants represent where the compressor takes its level detection from.
+#[derive(Enum, Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Topology {
+    /// The detector listens to the key signal *before* any gain is applied. This is the usual, modern design.
+    #[id = "feedforward"]
+    #[name = "Feed-forward"]
+    FeedForward,
+    /// The detector listens to the key with the compressor's own gain reduction (from the previous sample) applied, like a lot of vintage hardware.
+    /// Because the compressor is reacting to signal it has already compressed, this is gentler
+    /// (the effective ratio never gets much past 2:1) and the attack/release behave a bit differently.
+    #[id = "feedback"]
+    #[name = "Feedback"]
+    Feedback,
+}
+
+/// Variants represent where (and how) the attack/release smoothing is applied.
+/// See the Giannoulis, Massberg & Reiss paper linked at the top of this file for a comparison of these.
+#[derive(Enum, Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum EnvelopeType {
+    /// Smooth the detected level (in linear space) with a branching one-pole filter, *before* the gain computer.
+    /// This is the original design.
+    #[id = "level"]
+    #[name = "Level"]
+    Level,
+    /// Smooth the gain reduction (in dB) with a branching one-pole filter, *after* the gain computer.
+    /// Because the attack and release only ever see gain reduction, the threshold and knee aren't smeared by the envelope.
+    #[id = "branching"]
+    #[name = "Log Branching"]
+    SmoothBranching,
+    /// Like `SmoothBranching`, but the release runs first and the attack is applied on top of it,
+    /// so the two time constants don't interfere with each other. This gives the smoothest release of the three.
+    #[id = "decoupled"]
+    #[name = "Log Decoupled"]
+    SmoothDecoupled,
+}
+
+/// Holds one of each level detector, so that the detection type can be switched on the fly without allocating.
+#[derive(Default)]
+pub struct LevelDetectors {
+    pub rms: RmsLevelDetector,
+    pub peak: PeakLevelDetector,
+    pub true_peak: TruePeakLevelDetector,
+}
+impl LevelDetectors {
+    /// Returns the detector corresponding to the given `LevelDetectionType`.
+    pub fn get_mut(&mut self, detection_type: LevelDetectionType) -> &mut dyn LevelDetector {
+        match detection_type {
+            LevelDetectionType::Peak => &mut self.peak,
+            LevelDetectionType::Rms => &mut self.rms,
+            LevelDetectionType::TruePeak => &mut self.true_peak,
+        }
+    }
+    /// Clears the state of every detector. This doesn't allocate.
+    pub fn reset(&mut self) {
+        self.rms.reset();
+        self.true_peak = TruePeakLevelDetector::default();
+    }
+}
+
+/// The shape of the static curve, which maps an input level to an output level.
+/// Every ratio works on the distance from the threshold, and a ratio of `1.0` turns that part of the curve off.
+/// All of them share the same soft knee.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DynamicsCurve {
+    /// **In decibels.**
+    pub threshold: f32,
+    /// Downward compression above the threshold. For example, `4.0` means 4 dB in gives 1 dB out.
+    pub ratio: f32,
+    /// **In decibels.**
+    pub knee_width: f32,
+    /// Downward expansion below the threshold. For example, `2.0` means 1 dB in gives 2 dB out, and a very high ratio is a gate.
+    pub expansion_ratio: f32,
+    /// The most the expansion can turn the signal down, **in decibels**.
+    pub expansion_range: f32,
+    /// Upward compression below the threshold, which brings quiet signals up towards the threshold.
+    pub upward_compression_ratio: f32,
+    /// The most the upward compression can turn the signal up, **in decibels**.
+    pub upward_compression_range: f32,
+    /// Upward expansion above the threshold, which makes loud signals even louder.
+    pub upward_expansion_ratio: f32,
+    /// The most the upward expansion can turn the signal up, **in decibels**.
+    pub upward_expansion_range: f32,
+    /// The most the whole curve can turn the signal down, **in decibels**, no matter how high the ratios are.
+    /// `MAX_RANGE` (or anything above it) means there's no limit.
+    pub range: f32,
+}
+impl Default for DynamicsCurve {
+    fn default() -> Self {
+        Self {
+            threshold: DEFAULT_THRESHOLD,
+            ratio: DEFAULT_RATIO,
+            knee_width: DEFAULT_KNEE,
+            expansion_ratio: 1.0,
+            expansion_range: DEFAULT_EXPANSION_RANGE,
+            upward_compression_ratio: 1.0,
+            upward_compression_range: DEFAULT_UPWARD_RANGE,
+            upward_expansion_ratio: 1.0,
+            upward_expansion_range: DEFAULT_UPWARD_RANGE,
+            range: DEFAULT_RANGE,
+        }
+    }
+}
+impl DynamicsCurve {
+    /// The output level for a given input level, both **in decibels**.
+    /// This is a pure function, so it's what anything drawing or exporting the curve should use.
+    pub fn output_db(&self, input_db: f32) -> f32 {
+        let difference = input_db - self.threshold;
+        let above = knee_above(difference, self.knee_width);
+        let below = -knee_above(-difference, self.knee_width);
+
+        // each part of the curve adds its own gain change, and only the compressor has no range of its own
+        let compression = slope_gain(1.0 / self.ratio, above);
+        let upward_expansion =
+            slope_gain(self.upward_expansion_ratio, above).min(self.upward_expansion_range);
+        let expansion = slope_gain(self.expansion_ratio, below).max(-self.expansion_range);
+        let upward_compression = slope_gain(1.0 / self.upward_compression_ratio, below)
+            .min(self.upward_compression_range);
+
+        // the overall range caps the gain reduction, which is what `Compressor::calculate_gain_reduction()` ends up applying
+        let gain_change = compression + upward_expansion + expansion + upward_compression;
+        let gain_change = if self.range < MAX_RANGE {
+            gain_change.max(-self.range)
+        } else {
+            gain_change
+        };
+        input_db + gain_change
+    }
+    /// The gain **in decibels** that undoes what this curve does to a full-scale (0 dB) input.
+    /// This is the static makeup gain from Giannoulis et al.
+    pub fn makeup_db(&self) -> f32 {
+        -self.output_db(0.0)
+    }
+}
+
+/// How far past the threshold (**in decibels**) the input is, for the parts of the curve that work above the threshold.
+/// This is `0.0` below the knee, and `difference` above it, with a smooth transition inside the knee.
+fn knee_above(difference: f32, knee_width: f32) -> f32 {
+    // (this is strictly less than, so a knee width of 0 doesn't divide by 0 right at the threshold)
+    if 2.0 * (difference).abs() < knee_width {
+        // if we're within the knee range, use some special calculations!
+        (difference + (knee_width / 2.0)).powi(2) / (2.0 * knee_width)
+    } else if 2.0 * (difference) >= knee_width {
+        difference
+    } else {
+        0.0
+    }
+}
+
+/// The gain change (**in decibels**) when the part of the input past the threshold is scaled by `slope`.
+fn slope_gain(slope: f32, past_threshold: f32) -> f32 {
+    // a slope of 1 doesn't change anything, and skipping it avoids 0 * infinity for silent inputs
+    if slope == 1.0 {
+        0.0
+    } else {
+        (slope - 1.0) * past_threshold
+    }
+}
+
+/// Everything the `Compressor` needs to know to process a sample. See `CompressorParams` for what each of these does.
+///
+/// The plugin fills this in from its parameters once per sample,
+/// but it can also be built by hand, for example to render audio offline.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CompressorSettings {
+    #[serde(flatten)]
+    pub curve: DynamicsCurve,
+    /// **In seconds.**
+    pub attack_time: f32,
+    /// **In seconds.**
+    pub release_time: f32,
+    /// How long the gain reduction is held before the release starts. **In seconds.**
+    pub hold_time: f32,
+    /// **In seconds.**
+    pub lookahead: f32,
+    pub rms_mix: f32,
+    /// How much the release time adapts to the material (see `CrestFactorDetector`), from `0.0` (fixed) to `1.0`.
+    pub auto_release: f32,
+    pub level_detection_type: LevelDetectionType,
+    pub topology: Topology,
+    pub envelope_type: EnvelopeType,
+    pub character: Character,
+    pub transient_shaper: TransientShaperSettings,
+}
+impl CompressorSettings {
+    /// Returns a copy of these settings with the threshold (**in decibels**) and ratio shifted by the given amounts.
+    /// The ratio never goes below 1:1.
+    pub fn with_offsets(&self, threshold_offset: f32, ratio_offset: f32) -> Self {
+        Self {
+            curve: DynamicsCurve {
+                threshold: self.curve.threshold + threshold_offset,
+                ratio: (self.curve.ratio + ratio_offset).max(1.0),
+                ..self.curve
+            },
+            ..*self
+        }
+    }
+    /// The static curve as the gain computer really applies it, with whatever the `character` does to it. Both levels are **in decibels**.
+    pub fn output_db(&self, input_db: f32) -> f32 {
+        self.character.output_db(&self.curve, input_db)
+    }
+    /// The makeup gain **in decibels** for `MakeupMode::Static`. The transient shaper doesn't use the curve, so it doesn't get any.
+    ///
+    /// Like `DynamicsCurve::makeup_db()`, this undoes what the curve does to a full-scale (0 dB) input, but it goes through the `character`.
+    /// This isn't clamped: if the curve turns a full-scale input *up* (with upward expansion), the makeup turns it back down,
+    /// so that the loudness still stays the same.
+    pub fn static_makeup_db(&self) -> f32 {
+        if self.transient_shaper.enabled {
+            0.0
+        } else {
+            -self.output_db(0.0)
+        }
+    }
+}
+impl Default for CompressorSettings {
+    fn default() -> Self {
+        Self {
+            curve: DynamicsCurve::default(),
+            attack_time: DEFAULT_ATTACK_TIME,
+            release_time: DEFAULT_RELEASE_TIME,
+            hold_time: 0.0,
+            lookahead: 0.0,
+            rms_mix: 0.0,
+            auto_release: 0.0,
+            level_detection_type: LevelDetectionType::Rms,
+            topology: Topology::FeedForward,
+            envelope_type: EnvelopeType::Level,
+            character: Character::Vca,
+            transient_shaper: TransientShaperSettings::default(),
+        }
+    }
+}
+
+/// Combines the levels (from `Compressor::detect()`) of every channel into the shared level that `rms_mix` blends in.
+///
+/// The loudest channel wins, so with a full `rms_mix` every channel is turned down together (stereo-linked).
+/// Each detector only ever hears its own channel, since the stateful ones (like the true-peak filter) can't be fed a mix of channels.
+pub fn link_levels(levels: &[f32]) -> f32 {
+    levels.iter().copied().fold(0.0, f32::max)
 }
-*/
 
 /// Struct to represent a dynamic range compressor. See documentation for each field to learn more.
 pub struct Compressor {
     /// Average input gain *in linear space*.
     /// The method of calculating this average gain is controlled by the `level_detection_type` field.
+    ///
+    /// With the log-domain `EnvelopeType`s, this is *not* smoothed, since the smoothing happens after the gain computer instead.
     average_gain: f32,
-    /// RMS state.
-    pub rms: RmsLevelDetector,
+    /// Smoothed gain reduction **in decibels** (positive means the signal is turned down).
+    /// Only used by the log-domain `EnvelopeType`s.
+    gain_reduction_db: f32,
+    /// Intermediate release stage for `EnvelopeType::SmoothDecoupled`, also in decibels.
+    decoupled_release_db: f32,
+    /// How many more samples the envelope is held for before it's allowed to release.
+    hold_remaining: usize,
+    /// Level detector state, one for each `LevelDetectionType`.
+    pub detectors: LevelDetectors,
+    /// The key and its level from the last call to `detect()`, for `process()` to use.
+    key: f32,
+    level: f32,
+    /// Delays the signal the gain is applied to, for lookahead.
+    /// This is completely separate from the RMS buffer, so the lookahead and RMS window can be set independently.
+    pub delay_line: DelayLine,
+    /// The gain reduction factor from the previous sample. This is what feeds back into the detector with `Topology::Feedback`.
+    previous_gain_reduction: f32,
+    /// Measures how spiky the key is, for the auto release.
+    crest_factor: CrestFactorDetector,
+    /// State for each `Character`.
+    characters: CharacterModels,
+    /// Replaces the gain computer when `TransientShaperSettings::enabled` is set.
+    transient_shaper: TransientShaper,
 }
 
 impl Compressor {
-    /// Processes a single input sample and returns the processed sample.
+    /// Measures the level of a single key sample, and returns it *in linear space*.
+    /// This has to be called before every call to `process()`.
+    ///
+    /// `key` is the signal used for level detection. For a regular compressor, this is just the input sample,
+    /// but for sidechaining, this would be the sample from the aux input.
+    ///
+    /// Every channel's level should be measured before any of them is processed, so that they can be combined with `link_levels()`.
+    pub fn detect(&mut self, key: f32, settings: &CompressorSettings, sample_rate: f32) -> f32 {
+        // with feedback, the detector hears the current key with the previous sample's gain reduction applied.
+        // this isn't quite our previous output (that's an older, delayed sample), but it reacts the same way and also works with a sidechain
+        let model = self.characters.get_mut(settings.character);
+        let detector_input = match model.topology(settings) {
+            Topology::FeedForward => key,
+            Topology::Feedback => key * self.previous_gain_reduction,
+        };
+        let detection_type = model.level_detection_type(settings);
+
+        // this keeps running even while the auto release is off, so it already knows the material when it's turned on
+        let crest_coeff = calculate_filter_coefficient(AUTO_RELEASE_CREST_TIME, sample_rate);
+        self.crest_factor.process(detector_input, crest_coeff);
+        self.key = key;
+        self.level = self
+            .detectors
+            .get_mut(detection_type)
+            .calculate_level(detector_input);
+        self.level
+    }
+
+    /// Processes a single input sample and returns `(delayed_sample, processed_sample, gain_reduction)`.
+    ///
+    /// `shared_level` is the level of every channel together (see `link_levels()`), which is blended with this channel's own level by `rms_mix`.
     pub fn process(
         &mut self,
         sample: f32,
-        params: &CompressorParams,
-        shared_rms: &mut RmsLevelDetector,
+        shared_level: f32,
+        settings: &CompressorSettings,
         sample_rate: f32,
     ) -> (f32, f32, f32) {
-        let threshold = params.threshold.value();
-        let ratio = params.ratio.value();
-        let knee_width = params.knee_width.value();
-
         // TODO:
         // there might be a way to optimize this..
-        let attack_coeff =
-            calculate_filter_coefficient(params.attack_time.smoothed.next(), sample_rate);
-        let release_coeff =
-            calculate_filter_coefficient(params.release_time.smoothed.next(), sample_rate);
+        let attack_coeff = calculate_filter_coefficient(settings.attack_time, sample_rate);
+
+        let release_time = if settings.auto_release > 0.0 {
+            settings.release_time
+                * self
+                    .crest_factor
+                    .release_scale()
+                    .powf(settings.auto_release)
+        } else {
+            settings.release_time
+        };
+        let release_coeff = calculate_filter_coefficient(release_time, sample_rate);
+        let release_coeff = self.characters.get_mut(settings.character).release_coeff(
+            release_coeff,
+            -gain_to_db_fast(self.previous_gain_reduction),
+            sample_rate,
+        );
+        let hold_samples = (sample_rate * settings.hold_time) as usize;
 
-        // blends the shared/independent RMS
-        let rms_mix = params.rms_mix.smoothed.next();
-        self.update_gain(sample, shared_rms, rms_mix, attack_coeff, release_coeff);
+        let envelope_type = settings.envelope_type;
+        // blends the shared/independent levels
+        let rms_mix = settings.rms_mix;
+        let level = (1.0 - rms_mix) * self.level + rms_mix * shared_level;
+        self.update_gain(
+            level,
+            envelope_type,
+            attack_coeff,
+            release_coeff,
+            hold_samples,
+        );
 
         // we can implement lookahead by using/processing an older sample while updating our gain state with the current sample
         // thus, we'll effectively have our internal gain state being updated *ahead* of the samples we're processing
         // this does introduce some latency, of course
-        let lookahead_s = params.lookahead.smoothed.next();
-        // TODO:
-        // is there a better way than casting like this?
-        let lookahead_index = (sample_rate * lookahead_s) as i32;
-        let buffer_length = self.rms.buffer.len() as i32 - 1;
-        let buffer_index = (buffer_length - lookahead_index).max(0) as usize;
-        // if this is None, we have bigger problems
-        let target_sample = *self.rms.buffer.get(buffer_index).unwrap();
-
-        let c = self.calculate_gain_reduction(threshold, ratio, knee_width);
+        // rounded rather than truncated, so a lookahead that's a whole number of samples doesn't lose one to float error
+        let lookahead_samples = (sample_rate * settings.lookahead).round() as usize;
+        let target_sample = self.delay_line.process(sample, lookahead_samples);
+
+        let c = if settings.transient_shaper.enabled {
+            // the transient shaper reacts to the shape of the key rather than its level, so feedback doesn't make sense here
+            self.transient_shaper.process(
+                self.key,
+                &settings.transient_shaper,
+                attack_coeff,
+                release_coeff,
+                sample_rate,
+            )
+        } else {
+            self.calculate_gain_reduction(
+                &settings.curve,
+                settings.character,
+                envelope_type,
+                attack_coeff,
+                release_coeff,
+                hold_samples,
+            )
+        };
+        self.previous_gain_reduction = c;
         (target_sample, target_sample * c, c)
     }
 
-    /// Updates the internal gain of the compressor given an input sample.
+    /// Updates the internal gain of the compressor given the detected level of an input sample.
     fn update_gain(
         &mut self,
-        sample: f32,
-        shared_rms: &mut RmsLevelDetector,
-        rms_mix: f32,
+        new_gain: f32,
+        envelope_type: EnvelopeType,
         attack_coeff: f32,
         release_coeff: f32,
+        hold_samples: usize,
     ) {
         let avg_gain = self.average_gain;
 
-        let shared_gain = shared_rms.calculate_rms(sample);
-        let ind_gain = self.rms.calculate_rms(sample);
+        // the log-domain envelopes do their smoothing after the gain computer instead
+        if envelope_type != EnvelopeType::Level {
+            self.average_gain = new_gain;
+            return;
+        }
 
-        let new_gain = (1.0 - rms_mix) * ind_gain + rms_mix * shared_gain;
+        if self.holding(new_gain > avg_gain, hold_samples) {
+            return;
+        }
 
         // based on if our incoming signal is increasing or decreasing, choose the filter coefficent to use.
         let theta = if new_gain > avg_gain {
@@ -121,42 +696,2276 @@ impl Compressor {
         self.average_gain = n;
     }
 
-    /// This function converts the internal average gain of the compressor to decibels, then uses a soft-knee equation to calculate the gain reduction.
+    /// This function converts the internal average gain of the compressor to decibels, then uses the `DynamicsCurve` to calculate the gain reduction.
+    /// For the log-domain `EnvelopeType`s, the gain reduction is then smoothed with the attack/release coefficients.
     /// Returns a factor to multiply the input signal by.
-    fn calculate_gain_reduction(&mut self, threshold: f32, ratio: f32, knee_width: f32) -> f32 {
+    fn calculate_gain_reduction(
+        &mut self,
+        curve: &DynamicsCurve,
+        character: Character,
+        envelope_type: EnvelopeType,
+        attack_coeff: f32,
+        release_coeff: f32,
+        hold_samples: usize,
+    ) -> f32 {
         // first, we need to convert our gain to decibels.
         let input_db = gain_to_db_fast(self.average_gain);
 
         // GAIN COMPUTER
-        let reduced_db = {
-            let difference = input_db - threshold;
-            if 2.0 * (difference).abs() <= knee_width {
-                // if we're within the knee range, use some special calculations!
-                let gain_reduction = (difference + (knee_width / 2.0)).powi(2) / (2.0 * knee_width);
-                input_db + (1.0 / ratio - 1.0) * gain_reduction
-            } else if 2.0 * (difference) > knee_width {
-                // above the knee, apply compression
-                threshold + (difference / ratio)
-            } else {
-                // if we're below the knee/threshold
-                input_db
-            }
-        };
+        let reduced_db = self.characters.get(character).output_db(curve, input_db);
         // to be totally honest, i'm not sure why this has to be done.
         let final_db = reduced_db - input_db;
+        let final_db = match envelope_type {
+            EnvelopeType::Level => final_db,
+            _ => -self.smooth_gain_reduction(
+                -final_db,
+                envelope_type,
+                attack_coeff,
+                release_coeff,
+                hold_samples,
+            ),
+        };
         // convert back to linear space as a factor to multiply the input
         db_to_gain_fast(final_db)
     }
 
+    /// Runs one of the log-domain envelopes (from Giannoulis et al.) on the gain reduction, **in decibels**.
+    /// Returns the smoothed gain reduction, which is also positive when the signal should be turned down.
+    fn smooth_gain_reduction(
+        &mut self,
+        gain_reduction_db: f32,
+        envelope_type: EnvelopeType,
+        attack_coeff: f32,
+        release_coeff: f32,
+        hold_samples: usize,
+    ) -> f32 {
+        let previous = self.gain_reduction_db;
+        self.gain_reduction_db = match envelope_type {
+            EnvelopeType::SmoothDecoupled => {
+                // the release stage can only ever fall slowly, but it instantly follows anything louder
+                if !self.holding(gain_reduction_db > self.decoupled_release_db, hold_samples) {
+                    self.decoupled_release_db = gain_reduction_db.max(
+                        release_coeff * self.decoupled_release_db
+                            + (1.0 - release_coeff) * gain_reduction_db,
+                    );
+                }
+                // ...and the attack stage smooths that out
+                attack_coeff * previous + (1.0 - attack_coeff) * self.decoupled_release_db
+            }
+            _ if self.holding(gain_reduction_db > previous, hold_samples) => previous,
+            _ => {
+                // the same branching filter as `update_gain()`, but on the gain reduction
+                let theta = if gain_reduction_db > previous {
+                    attack_coeff
+                } else {
+                    release_coeff
+                };
+                theta * previous + (1.0 - theta) * gain_reduction_db
+            }
+        };
+        self.gain_reduction_db
+    }
+
+    /// Keeps track of the hold time. Returns `true` when the envelope should stay where it is instead of releasing.
+    ///
+    /// Every time the envelope is `attacking`, the hold starts over from `hold_samples`.
+    fn holding(&mut self, attacking: bool, hold_samples: usize) -> bool {
+        if attacking {
+            self.hold_remaining = hold_samples;
+            false
+        } else if self.hold_remaining > 0 {
+            self.hold_remaining -= 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Clears everything the compressor remembers: the envelopes, the detectors and the lookahead delay line.
+    /// The buffers are kept, so this doesn't allocate and is fine to call from `process()`.
+    pub fn reset(&mut self) {
+        self.average_gain = 0.0;
+        self.gain_reduction_db = 0.0;
+        self.decoupled_release_db = 0.0;
+        self.hold_remaining = 0;
+        self.detectors.reset();
+        self.key = 0.0;
+        self.level = 0.0;
+        self.delay_line.reset();
+        self.previous_gain_reduction = 1.0;
+        self.crest_factor = CrestFactorDetector::default();
+        self.characters = CharacterModels::default();
+        self.transient_shaper = TransientShaper::default();
+    }
+
+    /// The current (average) level of the detector *in linear space*, useful for metering and plotting.
+    pub fn average_gain(&self) -> f32 {
+        self.average_gain
+    }
+
     /// Construct a new `Compressor`. For more information on what each field actually does, see the `Compressor` docs.
     pub fn new() -> Self {
         let default_gain = 0.0;
         Compressor {
             average_gain: default_gain,
-            rms: RmsLevelDetector::default(),
+            gain_reduction_db: 0.0,
+            decoupled_release_db: 0.0,
+            hold_remaining: 0,
+            detectors: LevelDetectors::default(),
+            key: 0.0,
+            level: 0.0,
+            delay_line: DelayLine::default(),
+            previous_gain_reduction: 1.0,
+            crest_factor: CrestFactorDetector::default(),
+            characters: CharacterModels::default(),
+            transient_shaper: TransientShaper::default(),
+        }
+    }
+}
+impl Default for Compressor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Variants represent the hardware that the compressor behaves like. See the `CharacterModel` implementations for the details.
+#[derive(Enum, Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Character {
+    /// A clean VCA compressor, which does exactly what the settings say.
+    #[id = "vca"]
+    #[name = "VCA"]
+    Vca,
+    /// A fast, aggressive FET compressor with a feedback detector and a hard knee.
+    #[id = "fet"]
+    #[name = "FET"]
+    Fet,
+    /// A smooth optical compressor with a soft knee and a slow, program-dependent release.
+    #[id = "opto"]
+    Opto,
+    /// A variable-mu tube compressor, where the ratio rises the harder it's pushed.
+    #[id = "varimu"]
+    #[name = "Vari-mu"]
+    VariMu,
+}
+impl Character {
+    /// The static curve as shaped by this character (see `CharacterModel::output_db()`). Both levels are **in decibels**.
+    pub fn output_db(self, curve: &DynamicsCurve, input_db: f32) -> f32 {
+        CharacterModels::default()
+            .get(self)
+            .output_db(curve, input_db)
+    }
+}
+
+/// The parts of a `Compressor` that change with the `Character`.
+///
+/// Every method has a default that just follows the settings, so a model only needs to override what makes it different.
+pub trait CharacterModel {
+    /// Where the detector listens from.
+    fn topology(&self, settings: &CompressorSettings) -> Topology {
+        settings.topology
+    }
+    /// How the detector measures the level.
+    fn level_detection_type(&self, settings: &CompressorSettings) -> LevelDetectionType {
+        settings.level_detection_type
+    }
+    /// The static curve, which maps an input level to an output level, both **in decibels**.
+    fn output_db(&self, curve: &DynamicsCurve, input_db: f32) -> f32 {
+        curve.output_db(input_db)
+    }
+    /// Adjusts the release coefficient (from `calculate_filter_coefficient()`) for this sample.
+    /// `gain_reduction_db` is the gain reduction of the previous sample, positive when the signal is being turned down.
+    fn release_coeff(
+        &mut self,
+        release_coeff: f32,
+        _gain_reduction_db: f32,
+        _sample_rate: f32,
+    ) -> f32 {
+        release_coeff
+    }
+}
+
+/// See `Character::Vca`. This is the reference model, so it keeps all of the defaults.
+#[derive(Default)]
+pub struct VcaModel;
+impl CharacterModel for VcaModel {}
+
+/// How much narrower (as a factor) the knee of the FET model is.
+const FET_KNEE_SCALE: f32 = 0.25;
+
+/// See `Character::Fet`. Like the classic FET units, the detector sits after the gain element and reacts to peaks.
+#[derive(Default)]
+pub struct FetModel;
+impl CharacterModel for FetModel {
+    fn topology(&self, _settings: &CompressorSettings) -> Topology {
+        Topology::Feedback
+    }
+    fn level_detection_type(&self, _settings: &CompressorSettings) -> LevelDetectionType {
+        LevelDetectionType::Peak
+    }
+    fn output_db(&self, curve: &DynamicsCurve, input_db: f32) -> f32 {
+        DynamicsCurve {
+            knee_width: curve.knee_width * FET_KNEE_SCALE,
+            ..*curve
+        }
+        .output_db(input_db)
+    }
+}
+
+/// How much wider **(in decibels)** the knee of the opto model is.
+const OPTO_EXTRA_KNEE: f32 = 10.0;
+/// How long (in seconds) the opto cell takes to "remember" how hard it's been working.
+const OPTO_MEMORY_TIME: f32 = 2.0;
+/// How much longer (as a factor) the second stage of the opto release is.
+const OPTO_SLOW_RELEASE: f32 = 10.0;
+
+/// See `Character::Opto`. The light-dependent resistor in an optical compressor has a two-stage release:
+/// the first half of the gain reduction recovers quickly, and the rest recovers slowly.
+/// The longer it's been compressing, the more gain reduction is left for the slow stage.
+#[derive(Default)]
+pub struct OptoModel {
+    /// A slow average of the gain reduction **in decibels**, which is how much the cell has been lit up lately.
+    memory_db: f32,
+}
+impl CharacterModel for OptoModel {
+    fn topology(&self, _settings: &CompressorSettings) -> Topology {
+        Topology::Feedback
+    }
+    fn level_detection_type(&self, _settings: &CompressorSettings) -> LevelDetectionType {
+        LevelDetectionType::Rms
+    }
+    fn output_db(&self, curve: &DynamicsCurve, input_db: f32) -> f32 {
+        DynamicsCurve {
+            knee_width: curve.knee_width + OPTO_EXTRA_KNEE,
+            ..*curve
+        }
+        .output_db(input_db)
+    }
+    fn release_coeff(
+        &mut self,
+        release_coeff: f32,
+        gain_reduction_db: f32,
+        sample_rate: f32,
+    ) -> f32 {
+        let memory_coeff = calculate_filter_coefficient(OPTO_MEMORY_TIME, sample_rate);
+        self.memory_db = memory_coeff * self.memory_db + (1.0 - memory_coeff) * gain_reduction_db;
+        if gain_reduction_db > 0.5 * self.memory_db {
+            release_coeff
+        } else {
+            // the same as calculating the coefficient with a release time that's `OPTO_SLOW_RELEASE` times longer
+            release_coeff.powf(OPTO_SLOW_RELEASE.recip())
+        }
+    }
+}
+
+/// How far above the threshold **(in decibels)** the vari-mu model gets halfway to the full ratio.
+const VARI_MU_SPREAD: f32 = 10.0;
+
+/// See `Character::VariMu`. The ratio starts at 1:1 at the threshold and rises towards the `ratio` setting as the level goes up,
+/// which makes for a very soft knee that keeps on going.
+#[derive(Default)]
+pub struct VariMuModel;
+impl CharacterModel for VariMuModel {
+    fn topology(&self, _settings: &CompressorSettings) -> Topology {
+        Topology::Feedback
+    }
+    fn level_detection_type(&self, _settings: &CompressorSettings) -> LevelDetectionType {
+        LevelDetectionType::Rms
+    }
+    fn output_db(&self, curve: &DynamicsCurve, input_db: f32) -> f32 {
+        let above = knee_above(input_db - curve.threshold, curve.knee_width);
+        // this way, the output still always rises with the input
+        let ratio = 1.0 + (curve.ratio - 1.0) * above / (above + VARI_MU_SPREAD);
+        DynamicsCurve { ratio, ..*curve }.output_db(input_db)
+    }
+}
+
+/// Holds one of each `CharacterModel`, so that the character can be switched on the fly without allocating.
+#[derive(Default)]
+pub struct CharacterModels {
+    pub vca: VcaModel,
+    pub fet: FetModel,
+    pub opto: OptoModel,
+    pub vari_mu: VariMuModel,
+}
+impl CharacterModels {
+    /// Returns the model corresponding to the given `Character`.
+    pub fn get(&self, character: Character) -> &dyn CharacterModel {
+        match character {
+            Character::Vca => &self.vca,
+            Character::Fet => &self.fet,
+            Character::Opto => &self.opto,
+            Character::VariMu => &self.vari_mu,
+        }
+    }
+    /// Returns the model corresponding to the given `Character`.
+    pub fn get_mut(&mut self, character: Character) -> &mut dyn CharacterModel {
+        match character {
+            Character::Vca => &mut self.vca,
+            Character::Fet => &mut self.fet,
+            Character::Opto => &mut self.opto,
+            Character::VariMu => &mut self.vari_mu,
         }
     }
 }
+
+/// Samples the static curve (see `Character::output_db()`) at `num_points` evenly spaced input levels, from `min_db` to `max_db`.
+/// Returns `(input_db, output_db)` pairs.
+pub fn transfer_curve(
+    curve: DynamicsCurve,
+    character: Character,
+    min_db: f32,
+    max_db: f32,
+    num_points: usize,
+) -> impl Iterator<Item = (f32, f32)> {
+    let step = (max_db - min_db) / (num_points.max(2) - 1) as f32;
+    (0..num_points).map(move |i| {
+        let input_db = min_db + step * i as f32;
+        (input_db, character.output_db(&curve, input_db))
+    })
+}
+
 pub fn calculate_filter_coefficient(input: f32, sample_rate: f32) -> f32 {
     (-1.0 / (sample_rate * input)).exp()
 }
+
+/// Struct to represent a lookahead brickwall limiter for up to 2 (linked) channels.
+///
+/// For every sample, the gain needed to keep it under the ceiling is held for the whole lookahead window,
+/// and then averaged over the same window. This way the gain always ramps down smoothly,
+/// but has always reached its lowest point by the time the peak leaves the delay line.
+///
+/// The true-peak detectors only see an inter-sample peak `TRUE_PEAK_DELAY` samples after the samples around it came in,
+/// so the audio is delayed (and the gain held) for that much longer, to line the gain up with those samples.
+pub struct Limiter {
+    /// Delays the audio by the lookahead (plus `TRUE_PEAK_DELAY`), one for each channel.
+    delay_lines: [DelayLine; 2],
+    /// Used so that peaks in between samples are also caught.
+    true_peak_detectors: [TruePeakLevelDetector; 2],
+    /// The gain needed for each of the last `lookahead + TRUE_PEAK_DELAY + 1` samples.
+    required_gain: DelayLine,
+    /// The held gain after the release has been applied.
+    release_gain: f32,
+    /// The last `lookahead` values of `release_gain`, which get averaged together.
+    release_history: DelayLine,
+    lookahead: usize,
+}
+impl Default for Limiter {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+impl Limiter {
+    /// Construct a new `Limiter` which looks ahead by `lookahead` samples. See `latency()` for how much it delays the audio.
+    pub fn new(lookahead: usize) -> Self {
+        let delay = lookahead + TRUE_PEAK_DELAY;
+        let mut limiter = Self {
+            delay_lines: [DelayLine::new(delay), DelayLine::new(delay)],
+            true_peak_detectors: Default::default(),
+            required_gain: DelayLine::new(delay),
+            release_gain: 1.0,
+            release_history: DelayLine::new(lookahead),
+            lookahead,
+        };
+        limiter.reset();
+        limiter
+    }
+    /// Clears the audio in the delay lines and lets go of any gain reduction.
+    /// This doesn't allocate, so it's fine to call from `process()`, for example when the limiter is switched back on.
+    pub fn reset(&mut self) {
+        for delay_line in &mut self.delay_lines {
+            delay_line.reset();
+        }
+        self.true_peak_detectors = Default::default();
+        self.required_gain.reset_to(1.0);
+        self.release_gain = 1.0;
+        self.release_history.reset_to(1.0);
+    }
+    /// The latency of the limiter, in samples.
+    pub fn latency(&self) -> u32 {
+        (self.lookahead + TRUE_PEAK_DELAY) as u32
+    }
+    /// Limits a single sample from each channel in place, so that none of them go over `ceiling` (**in linear space**).
+    /// `frame` can have 1 or 2 channels, and all channels get the same gain.
+    ///
+    /// Returns the gain that was applied.
+    pub fn process(&mut self, frame: &mut [f32], ceiling: f32, release_coeff: f32) -> f32 {
+        // how loud is the loudest channel, including anything in between samples?
+        let peak = frame
+            .iter()
+            .zip(&mut self.true_peak_detectors)
+            .map(|(sample, detector)| detector.calculate_level(*sample).max(sample.abs()))
+            .fold(0.0, f32::max);
+        let required_gain = if peak > ceiling { ceiling / peak } else { 1.0 };
+        self.required_gain.process(required_gain, 0);
+
+        // hold the lowest gain for the whole window (and for long enough to cover the samples around a late inter-sample peak)...
+        let delay = self.lookahead + TRUE_PEAK_DELAY;
+        let held_gain = (0..=delay)
+            .map(|delay| self.required_gain.get(delay))
+            .fold(1.0, f32::min);
+        // ...and release from it slowly (but never attack slowly, that's what the lookahead is for)
+        self.release_gain = if held_gain < self.release_gain {
+            held_gain
+        } else {
+            release_coeff * self.release_gain + (1.0 - release_coeff) * held_gain
+        };
+        self.release_history.process(self.release_gain, 0);
+
+        // finally, smooth out the attack by averaging over the window
+        let window = self.lookahead.max(1);
+        let gain = (0..window)
+            .map(|delay| self.release_history.get(delay))
+            .sum::<f32>()
+            / window as f32;
+
+        for (sample, delay_line) in frame.iter_mut().zip(&mut self.delay_lines) {
+            let delayed = delay_line.process(*sample, delay);
+            // the averaging above already makes sure of this, but rounding errors shouldn't be able to cause an over
+            *sample = (delayed * gain).clamp(-ceiling, ceiling);
+        }
+        gain
+    }
+}
+
+/// Keeps a long-term average of the measured gain reduction, so it can be made up for automatically.
+/// Unlike `DynamicsCurve::makeup_db()`, this follows what the compressor is actually doing to the material.
+#[derive(Default)]
+pub struct GainReductionAverage {
+    /// **In decibels**, positive when the signal is being turned down.
+    average_db: f32,
+}
+impl GainReductionAverage {
+    /// Feeds in the gain reduction factor for the latest sample (the same one `Compressor::process()` returns).
+    /// `coeff` should be calculated with `calculate_filter_coefficient()`, using a long time (a few seconds).
+    pub fn process(&mut self, gain_reduction: f32, coeff: f32) {
+        let gain_reduction_db = -gain_to_db_fast(gain_reduction);
+        self.average_db = coeff * self.average_db + (1.0 - coeff) * gain_reduction_db;
+    }
+    /// The gain **in decibels** that makes up for the average gain reduction.
+    pub fn makeup_db(&self) -> f32 {
+        self.average_db
+    }
+}
+
+/// How long (in seconds) `CrestFactorDetector` looks back when measuring the crest factor.
+pub const AUTO_RELEASE_CREST_TIME: f32 = 0.2;
+/// The squared crest factor where the auto release uses the release time as-is.
+/// 4.0 is a crest factor of 6 dB, somewhere between a sine and a drum loop.
+const AUTO_RELEASE_REFERENCE_CREST: f32 = 4.0;
+/// The most that the auto release can stretch or shrink the release time by, in either direction.
+const AUTO_RELEASE_MAX_SCALE: f32 = 4.0;
+
+/// Measures the crest factor (the ratio between the peak and RMS level) of a signal, for program-dependent release.
+///
+/// Transients have a high crest factor, so the release gets shorter and the compressor lets go of them quickly.
+/// Sustained, dense material has a low crest factor, so the release gets longer and the gain reduction doesn't pump.
+/// This is the adaptive release from Giannoulis et al., "Parameter Automation in a Dynamic Range Compressor".
+#[derive(Default)]
+pub struct CrestFactorDetector {
+    /// Squared peak level. Jumps up instantly, then decays.
+    peak_squared: f32,
+    /// Mean of the squared signal.
+    mean_squared: f32,
+}
+impl CrestFactorDetector {
+    /// `coeff` should be calculated with `calculate_filter_coefficient()`, see `AUTO_RELEASE_CREST_TIME`.
+    pub fn process(&mut self, sample: f32, coeff: f32) {
+        let squared = sample * sample;
+        self.peak_squared = squared.max(coeff * self.peak_squared + (1.0 - coeff) * squared);
+        self.mean_squared = coeff * self.mean_squared + (1.0 - coeff) * squared;
+    }
+    /// The squared crest factor, or `AUTO_RELEASE_REFERENCE_CREST` when there's no signal to measure.
+    pub fn crest_factor_squared(&self) -> f32 {
+        if self.mean_squared > f32::EPSILON {
+            self.peak_squared / self.mean_squared
+        } else {
+            AUTO_RELEASE_REFERENCE_CREST
+        }
+    }
+    /// What the release time should be multiplied by, from `1.0 / AUTO_RELEASE_MAX_SCALE` to `AUTO_RELEASE_MAX_SCALE`.
+    pub fn release_scale(&self) -> f32 {
+        (AUTO_RELEASE_REFERENCE_CREST / self.crest_factor_squared())
+            .clamp(AUTO_RELEASE_MAX_SCALE.recip(), AUTO_RELEASE_MAX_SCALE)
+    }
+}
+
+/// The most bands that `MultibandCompressor` can split the signal into.
+pub const MAX_BANDS: usize = 4;
+/// The Q of a 2nd-order Butterworth filter. Two of these in series make a 4th-order Linkwitz-Riley filter.
+const BUTTERWORTH_Q: f32 = std::f32::consts::FRAC_1_SQRT_2;
+
+/// Struct to represent a second-order IIR filter, using the transposed direct form II.
+/// The coefficients come from the RBJ Audio EQ Cookbook, and are already normalized by `a0`.
+#[derive(Debug, Clone, Copy)]
+pub struct Biquad {
+    b0: f32,
+    b1: f32,
+    b2: f32,
+    a1: f32,
+    a2: f32,
+    z1: f32,
+    z2: f32,
+}
+impl Default for Biquad {
+    /// A filter that passes everything through untouched.
+    fn default() -> Self {
+        Self {
+            b0: 1.0,
+            b1: 0.0,
+            b2: 0.0,
+            a1: 0.0,
+            a2: 0.0,
+            z1: 0.0,
+            z2: 0.0,
+        }
+    }
+}
+impl Biquad {
+    /// Turns this filter into a lowpass. The filter state is kept, so this is safe to call while processing.
+    pub fn set_lowpass(&mut self, frequency: f32, q: f32, sample_rate: f32) {
+        let (cos, alpha) = Self::intermediates(frequency, q, sample_rate);
+        let b1 = 1.0 - cos;
+        self.set_coefficients(b1 / 2.0, b1, b1 / 2.0, 1.0 + alpha, -2.0 * cos, 1.0 - alpha);
+    }
+    /// Turns this filter into a highpass. The filter state is kept, so this is safe to call while processing.
+    pub fn set_highpass(&mut self, frequency: f32, q: f32, sample_rate: f32) {
+        let (cos, alpha) = Self::intermediates(frequency, q, sample_rate);
+        let b1 = -(1.0 + cos);
+        self.set_coefficients(
+            -b1 / 2.0,
+            b1,
+            -b1 / 2.0,
+            1.0 + alpha,
+            -2.0 * cos,
+            1.0 - alpha,
+        );
+    }
+    /// Turns this filter into a bandpass with 0 dB of gain at `frequency`. The filter state is kept, so this is safe to call while processing.
+    pub fn set_bandpass(&mut self, frequency: f32, q: f32, sample_rate: f32) {
+        let (cos, alpha) = Self::intermediates(frequency, q, sample_rate);
+        self.set_coefficients(alpha, 0.0, -alpha, 1.0 + alpha, -2.0 * cos, 1.0 - alpha);
+    }
+    /// Turns this filter into a high shelf, which changes everything above `frequency` by `gain_db` **(in decibels)**.
+    /// The filter state is kept, so this is safe to call while processing.
+    pub fn set_high_shelf(&mut self, frequency: f32, q: f32, gain_db: f32, sample_rate: f32) {
+        let (cos, alpha) = Self::intermediates(frequency, q, sample_rate);
+        let a = 10.0f32.powf(gain_db / 40.0);
+        let sqrt_a_alpha = 2.0 * a.sqrt() * alpha;
+        self.set_coefficients(
+            a * ((a + 1.0) + (a - 1.0) * cos + sqrt_a_alpha),
+            -2.0 * a * ((a - 1.0) + (a + 1.0) * cos),
+            a * ((a + 1.0) + (a - 1.0) * cos - sqrt_a_alpha),
+            (a + 1.0) - (a - 1.0) * cos + sqrt_a_alpha,
+            2.0 * ((a - 1.0) - (a + 1.0) * cos),
+            (a + 1.0) - (a - 1.0) * cos - sqrt_a_alpha,
+        );
+    }
+    /// Returns `cos(w0)` and `alpha` from the cookbook.
+    fn intermediates(frequency: f32, q: f32, sample_rate: f32) -> (f32, f32) {
+        // keep the frequency below nyquist, otherwise the filter blows up
+        let frequency = frequency.clamp(1.0, sample_rate * 0.49);
+        let w0 = std::f32::consts::TAU * frequency / sample_rate;
+        (w0.cos(), w0.sin() / (2.0 * q))
+    }
+    fn set_coefficients(&mut self, b0: f32, b1: f32, b2: f32, a0: f32, a1: f32, a2: f32) {
+        self.b0 = b0 / a0;
+        self.b1 = b1 / a0;
+        self.b2 = b2 / a0;
+        self.a1 = a1 / a0;
+        self.a2 = a2 / a0;
+    }
+    pub fn process(&mut self, input: f32) -> f32 {
+        let output = self.b0 * input + self.z1;
+        self.z1 = self.b1 * input - self.a1 * output + self.z2;
+        self.z2 = self.b2 * input - self.a2 * output;
+        output
+    }
+}
+
+/// A 4th-order (24 dB/octave) Linkwitz-Riley crossover.
+/// The low and high outputs are always in phase with each other, so they add back up to the input with a flat frequency response
+/// (the sum is an allpassed version of the input).
+#[derive(Debug, Default, Clone, Copy)]
+pub struct LinkwitzRileyCrossover {
+    /// The frequency and sample rate the coefficients were last calculated for, so we don't recalculate them every sample.
+    frequency: f32,
+    sample_rate: f32,
+    lowpass: [Biquad; 2],
+    highpass: [Biquad; 2],
+}
+impl LinkwitzRileyCrossover {
+    /// Sets the crossover frequency **in Hz**. This doesn't do anything if neither the frequency nor the sample rate has changed.
+    pub fn set_frequency(&mut self, frequency: f32, sample_rate: f32) {
+        if frequency == self.frequency && sample_rate == self.sample_rate {
+            return;
+        }
+        self.frequency = frequency;
+        self.sample_rate = sample_rate;
+        for filter in &mut self.lowpass {
+            filter.set_lowpass(frequency, BUTTERWORTH_Q, sample_rate);
+        }
+        for filter in &mut self.highpass {
+            filter.set_highpass(frequency, BUTTERWORTH_Q, sample_rate);
+        }
+    }
+    /// Splits a sample into its `(low, high)` parts.
+    pub fn process(&mut self, input: f32) -> (f32, f32) {
+        let low = self
+            .lowpass
+            .iter_mut()
+            .fold(input, |sample, filter| filter.process(sample));
+        let high = self
+            .highpass
+            .iter_mut()
+            .fold(input, |sample, filter| filter.process(sample));
+        (low, high)
+    }
+}
+
+/// Splits a signal into up to `MAX_BANDS` bands, using a chain of `LinkwitzRileyCrossover`s.
+///
+/// Each split only happens to the part of the signal above the previous crossover,
+/// so the lower bands are also run through the phase shift of every crossover above them.
+/// This way, all the bands stay phase-coherent and add back up to a flat response.
+#[derive(Debug, Default)]
+pub struct BandSplitter {
+    crossovers: [LinkwitzRileyCrossover; MAX_BANDS - 1],
+    /// `allpasses[band][i]` matches the phase shift of `crossovers[band + 1 + i]`.
+    allpasses: [[LinkwitzRileyCrossover; MAX_BANDS - 2]; MAX_BANDS - 2],
+}
+impl BandSplitter {
+    /// Sets the crossover frequencies **in Hz**, from low to high.
+    pub fn set_frequencies(&mut self, frequencies: &[f32; MAX_BANDS - 1], sample_rate: f32) {
+        for (crossover, frequency) in self.crossovers.iter_mut().zip(frequencies) {
+            crossover.set_frequency(*frequency, sample_rate);
+        }
+        for (band, allpasses) in self.allpasses.iter_mut().enumerate() {
+            for (i, allpass) in allpasses.iter_mut().enumerate() {
+                if let Some(frequency) = frequencies.get(band + 1 + i) {
+                    allpass.set_frequency(*frequency, sample_rate);
+                }
+            }
+        }
+    }
+    /// Clears the filters that weren't used with `num_bands` bands, for when more bands are switched on.
+    /// The ones that were in use keep their state, so the bands that were already there carry on without clicking.
+    pub fn reset_unused(&mut self, num_bands: usize) {
+        let num_bands = num_bands.clamp(1, MAX_BANDS);
+        for crossover in &mut self.crossovers[num_bands - 1..] {
+            *crossover = LinkwitzRileyCrossover::default();
+        }
+        // this is the same count as in `process()`
+        for (band, allpasses) in self.allpasses.iter_mut().enumerate() {
+            let num_allpasses = (num_bands - 1).saturating_sub(band + 1);
+            for allpass in &mut allpasses[num_allpasses..] {
+                *allpass = LinkwitzRileyCrossover::default();
+            }
+        }
+    }
+    /// Splits a sample into `num_bands` bands, from low to high. Any bands past `num_bands` are `0.0`.
+    pub fn process(&mut self, input: f32, num_bands: usize) -> [f32; MAX_BANDS] {
+        let num_bands = num_bands.clamp(1, MAX_BANDS);
+        let mut bands = [0.0; MAX_BANDS];
+
+        let mut rest = input;
+        for (band, crossover) in self.crossovers[..num_bands - 1].iter_mut().enumerate() {
+            let (low, high) = crossover.process(rest);
+            bands[band] = low;
+            rest = high;
+        }
+        bands[num_bands - 1] = rest;
+
+        // now, line up the phase of the lower bands with the higher ones
+        for (band, allpasses) in self.allpasses.iter_mut().enumerate() {
+            let num_allpasses = (num_bands - 1).saturating_sub(band + 1);
+            for allpass in &mut allpasses[..num_allpasses] {
+                let (low, high) = allpass.process(bands[band]);
+                bands[band] = low + high;
+            }
+        }
+        bands
+    }
+}
+
+/// The settings for a single band of a `MultibandCompressor`.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct BandSettings {
+    pub compressor: CompressorSettings,
+    /// When any band is soloed, only the soloed bands are heard.
+    pub solo: bool,
+    /// Passes the band through without compressing it.
+    pub bypass: bool,
+}
+
+/// Everything the `MultibandCompressor` needs to know to process a sample.
+#[derive(Debug, Clone, Copy)]
+pub struct MultibandSettings {
+    /// How many bands to split the signal into, from 1 to `MAX_BANDS`.
+    pub num_bands: usize,
+    /// The crossover frequencies **in Hz**, from low to high. Only the first `num_bands - 1` are used.
+    pub crossovers: [f32; MAX_BANDS - 1],
+    pub bands: [BandSettings; MAX_BANDS],
+}
+impl MultibandSettings {
+    /// The makeup gain **in decibels** for `MakeupMode::Static`.
+    /// Every band has its own curve, so there's no single right answer here. The average over the bands that are compressed is close enough,
+    /// so bypassed bands are left out (and if they're all bypassed, there's nothing to make up for).
+    pub fn static_makeup_db(&self) -> f32 {
+        let compressed = self.bands[..self.num_bands.clamp(1, MAX_BANDS)]
+            .iter()
+            .filter(|band| !band.bypass);
+        let (total, count) = compressed.fold((0.0, 0), |(total, count), band| {
+            (total + band.compressor.static_makeup_db(), count + 1)
+        });
+        if count > 0 {
+            total / count as f32
+        } else {
+            0.0
+        }
+    }
+    /// The same as `CompressorSettings::with_offsets()`, applied to every band.
+    pub fn with_offsets(&self, threshold_offset: f32, ratio_offset: f32) -> Self {
+        let mut settings = *self;
+        for band in &mut settings.bands {
+            band.compressor = band.compressor.with_offsets(threshold_offset, ratio_offset);
+        }
+        settings
+    }
+}
+
+/// Splits the signal into bands, and runs a separate `Compressor` on each one.
+#[derive(Default)]
+pub struct MultibandCompressor {
+    splitter: BandSplitter,
+    /// The key is split with the same crossovers, so each band only reacts to its own part of the key.
+    key_splitter: BandSplitter,
+    /// One `Compressor` for each band, from low to high.
+    pub compressors: [Compressor; MAX_BANDS],
+}
+impl MultibandCompressor {
+    /// Gets the bands from `active_bands` up ready to be switched on, since they haven't been running.
+    /// With an `active_bands` of 0, everything is reset (for example, when the full-band compressor was running instead).
+    pub fn reset_from(&mut self, active_bands: usize) {
+        self.splitter.reset_unused(active_bands);
+        self.key_splitter.reset_unused(active_bands);
+        for compressor in &mut self.compressors[active_bands.min(MAX_BANDS)..] {
+            compressor.reset();
+        }
+    }
+    /// Measures the level of a single key sample in each band. This works just like `Compressor::detect()`.
+    pub fn detect(
+        &mut self,
+        key: f32,
+        settings: &MultibandSettings,
+        sample_rate: f32,
+    ) -> [f32; MAX_BANDS] {
+        let num_bands = settings.num_bands.clamp(1, MAX_BANDS);
+        self.key_splitter
+            .set_frequencies(&settings.crossovers, sample_rate);
+        let key_bands = self.key_splitter.process(key, num_bands);
+
+        let mut levels = [0.0; MAX_BANDS];
+        for band in 0..num_bands {
+            levels[band] = self.compressors[band].detect(
+                key_bands[band],
+                &settings.bands[band].compressor,
+                sample_rate,
+            );
+        }
+        levels
+    }
+
+    /// Processes a single input sample. This works just like `Compressor::process()`,
+    /// except that there's a shared level for each band, and the gain reduction is returned separately for each band.
+    pub fn process(
+        &mut self,
+        sample: f32,
+        shared_levels: &[f32; MAX_BANDS],
+        settings: &MultibandSettings,
+        sample_rate: f32,
+    ) -> (f32, f32, [f32; MAX_BANDS]) {
+        let num_bands = settings.num_bands.clamp(1, MAX_BANDS);
+        self.splitter
+            .set_frequencies(&settings.crossovers, sample_rate);
+        let bands = self.splitter.process(sample, num_bands);
+
+        let any_solo = settings.bands[..num_bands].iter().any(|band| band.solo);
+
+        let mut dry = 0.0;
+        let mut wet = 0.0;
+        let mut gain_reductions = [1.0; MAX_BANDS];
+        for band in 0..num_bands {
+            let band_settings = &settings.bands[band];
+            let (delayed, processed, gain_reduction) = self.compressors[band].process(
+                bands[band],
+                shared_levels[band],
+                &band_settings.compressor,
+                sample_rate,
+            );
+            let processed = if band_settings.bypass {
+                delayed
+            } else {
+                gain_reductions[band] = gain_reduction;
+                processed
+            };
+            if any_solo && !band_settings.solo {
+                continue;
+            }
+            dry += delayed;
+            wet += processed;
+        }
+        (dry, wet, gain_reductions)
+    }
+}
+
+/// Variants represent the filter that the key signal goes through before it reaches the level detectors.
+#[derive(Enum, Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SidechainFilterType {
+    /// The detectors hear the whole key signal.
+    #[id = "off"]
+    Off,
+    /// Takes the lows out of the key, so the bass and kick don't drive the compressor as hard.
+    #[id = "highpass"]
+    #[name = "High-Pass"]
+    HighPass,
+    /// Takes the highs out of the key.
+    #[id = "lowpass"]
+    #[name = "Low-Pass"]
+    LowPass,
+    /// Only keeps the part of the key around the frequency, for example to go after sibilance.
+    #[id = "bandpass"]
+    #[name = "Band-Pass"]
+    BandPass,
+    /// A gentle tilt around the frequency, `SIDECHAIN_TILT` dB down below it and up above it.
+    #[id = "tilt"]
+    Tilt,
+}
+
+/// How far **(in decibels)** `SidechainFilterType::Tilt` turns the lows down and the highs up.
+pub const SIDECHAIN_TILT: f32 = 6.0;
+
+/// Everything the `SidechainFilter` needs to know. See `CompressorParams` for what each of these does.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SidechainFilterSettings {
+    pub filter_type: SidechainFilterType,
+    /// **In Hz.**
+    pub frequency: f32,
+    pub q: f32,
+}
+impl Default for SidechainFilterSettings {
+    fn default() -> Self {
+        Self {
+            filter_type: SidechainFilterType::Off,
+            frequency: 100.0,
+            q: BUTTERWORTH_Q,
+        }
+    }
+}
+
+/// Filters the key signal before it reaches the level detectors. The audio itself is never filtered.
+#[derive(Debug, Default)]
+pub struct SidechainFilter {
+    filter: Biquad,
+    /// The settings and sample rate the coefficients were last calculated for, so we don't recalculate them every sample.
+    settings: SidechainFilterSettings,
+    sample_rate: f32,
+}
+impl SidechainFilter {
+    /// Filters a single key sample.
+    pub fn process(
+        &mut self,
+        key: f32,
+        settings: &SidechainFilterSettings,
+        sample_rate: f32,
+    ) -> f32 {
+        if *settings != self.settings || sample_rate != self.sample_rate {
+            self.settings = *settings;
+            self.sample_rate = sample_rate;
+            let SidechainFilterSettings {
+                filter_type,
+                frequency,
+                q,
+            } = *settings;
+            match filter_type {
+                SidechainFilterType::Off => self.filter = Biquad::default(),
+                SidechainFilterType::HighPass => {
+                    self.filter.set_highpass(frequency, q, sample_rate)
+                }
+                SidechainFilterType::LowPass => self.filter.set_lowpass(frequency, q, sample_rate),
+                SidechainFilterType::BandPass => {
+                    self.filter.set_bandpass(frequency, q, sample_rate)
+                }
+                // the shelf does the whole tilt, and then everything is turned down by half of it
+                SidechainFilterType::Tilt => {
+                    self.filter
+                        .set_high_shelf(frequency, q, 2.0 * SIDECHAIN_TILT, sample_rate)
+                }
+            }
+        }
+
+        match settings.filter_type {
+            SidechainFilterType::Off => key,
+            SidechainFilterType::Tilt => {
+                self.filter.process(key) * db_to_gain_fast(-SIDECHAIN_TILT)
+            }
+            _ => self.filter.process(key),
+        }
+    }
+}
+
+/// Variants represent how the de-esser turns the sibilance down, once the band-passed key has gone over the threshold.
+#[derive(Enum, Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DeEsserMode {
+    /// Just a regular compressor.
+    #[id = "off"]
+    Off,
+    /// The whole signal is turned down. This is the most transparent on sibilance, but it can duck the rest of the vocal too.
+    #[id = "wideband"]
+    Wideband,
+    /// Only the sibilant band (around the de-esser frequency) is turned down, and the rest of the signal is left alone.
+    #[id = "splitband"]
+    #[name = "Split-Band"]
+    SplitBand,
+}
+
+/// The Q of the de-esser's band-pass filters. Sibilance is pretty wide, so this is on the gentle side.
+const DE_ESSER_Q: f32 = 1.0;
+
+/// Everything the de-esser needs to know. See `CompressorParams` for what each of these does.
+///
+/// The de-esser isn't a separate processor, it's just a `Compressor` with a band-passed key,
+/// so the threshold, ratio, attack, release etc. all still apply.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DeEsserSettings {
+    pub mode: DeEsserMode,
+    /// The center of the sibilant band, **in Hz**.
+    pub frequency: f32,
+}
+impl DeEsserSettings {
+    pub fn is_enabled(&self) -> bool {
+        self.mode != DeEsserMode::Off
+    }
+    /// The band-pass that the key goes through when the de-esser is on, in place of the regular sidechain filter.
+    pub fn sidechain_filter(&self) -> SidechainFilterSettings {
+        SidechainFilterSettings {
+            filter_type: SidechainFilterType::BandPass,
+            frequency: self.frequency,
+            q: DE_ESSER_Q,
+        }
+    }
+}
+impl Default for DeEsserSettings {
+    fn default() -> Self {
+        Self {
+            mode: DeEsserMode::Off,
+            frequency: DEFAULT_DE_ESSER_FREQUENCY,
+        }
+    }
+}
+
+/// Applies the gain reduction to the sibilant band only, for `DeEsserMode::SplitBand`.
+///
+/// The band is split off with the same band-pass as the key. Whatever's left is the input minus the band (a notch),
+/// so with no gain reduction, the output is exactly the input.
+#[derive(Debug, Default)]
+pub struct SplitBandDeEsser {
+    band: Biquad,
+    /// The frequency and sample rate the coefficients were last calculated for, so we don't recalculate them every sample.
+    frequency: f32,
+    sample_rate: f32,
+}
+impl SplitBandDeEsser {
+    /// Turns the sibilant band of `sample` down by `gain_reduction` (the factor `Compressor::process()` returns).
+    pub fn process(
+        &mut self,
+        sample: f32,
+        gain_reduction: f32,
+        frequency: f32,
+        sample_rate: f32,
+    ) -> f32 {
+        if frequency != self.frequency || sample_rate != self.sample_rate {
+            self.frequency = frequency;
+            self.sample_rate = sample_rate;
+            self.band.set_bandpass(frequency, DE_ESSER_Q, sample_rate);
+        }
+        let band = self.band.process(sample);
+        sample + band * (gain_reduction - 1.0)
+    }
+}
+
+/// Variants represent where the saturation happens, if anywhere.
+#[derive(Enum, Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SaturationPosition {
+    #[id = "off"]
+    Off,
+    /// Before the gain element, like the input stage of a hardware unit. The gain stage and the dry signal get the saturated signal,
+    /// but the level detection still listens to the clean key.
+    #[id = "pre"]
+    Pre,
+    /// After the gain element, on the compressed signal only.
+    #[id = "post"]
+    Post,
+}
+
+/// Variants represent the shape of the saturation curve.
+#[derive(Enum, Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SaturationType {
+    /// A symmetric `tanh` soft clipper. This only adds odd harmonics.
+    #[id = "tape"]
+    Tape,
+    /// An asymmetric soft clipper, which adds even harmonics as well.
+    #[id = "tube"]
+    Tube,
+    /// Clips hard at full scale. This is the harshest of the three.
+    #[id = "hardclip"]
+    #[name = "Hard Clip"]
+    HardClip,
+}
+
+/// How far the tube curve is shifted along `tanh`, which is what makes it asymmetric.
+const TUBE_BIAS: f32 = 0.3;
+/// The lowest frequency (in Hz) that makes it through the DC blocker after the tube curve.
+const DC_BLOCKER_FREQUENCY: f32 = 10.0;
+
+/// Everything the `Saturator` needs to know. See `CompressorParams` for what each of these does.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SaturationSettings {
+    pub position: SaturationPosition,
+    pub saturation_type: SaturationType,
+    /// **In decibels.**
+    pub drive: f32,
+    pub mix: f32,
+}
+impl Default for SaturationSettings {
+    fn default() -> Self {
+        Self {
+            position: SaturationPosition::Off,
+            saturation_type: SaturationType::Tape,
+            drive: 0.0,
+            mix: 1.0,
+        }
+    }
+}
+
+/// A waveshaper that adds harmonics. It's memoryless apart from the DC blocker that the tube curve needs,
+/// so it aliases like any other nonlinearity. Running it at an oversampled rate is what keeps that down.
+#[derive(Debug, Default)]
+pub struct Saturator {
+    /// The previous input and output of the DC blocker.
+    dc_input: f32,
+    dc_output: f32,
+}
+impl Saturator {
+    /// Saturates a single sample.
+    ///
+    /// The drive turns the signal up going into the curve and back down coming out of it,
+    /// so quiet signals come out at the same level and only the loud parts are shaped.
+    pub fn process(&mut self, sample: f32, settings: &SaturationSettings, sample_rate: f32) -> f32 {
+        let drive = db_to_gain_fast(settings.drive);
+        let driven = sample * drive;
+        let shaped = match settings.saturation_type {
+            SaturationType::Tape => driven.tanh(),
+            SaturationType::Tube => {
+                // shifted so that silence stays silent, and scaled so that quiet signals still have a gain of 1
+                let slope = 1.0 - TUBE_BIAS.tanh().powi(2);
+                let shaped = ((driven + TUBE_BIAS).tanh() - TUBE_BIAS.tanh()) / slope;
+                self.block_dc(shaped, sample_rate)
+            }
+            SaturationType::HardClip => driven.clamp(-1.0, 1.0),
+        };
+        let saturated = shaped / drive;
+        (1.0 - settings.mix) * sample + settings.mix * saturated
+    }
+    /// A one-pole highpass, for the DC offset that the asymmetric curve adds.
+    fn block_dc(&mut self, input: f32, sample_rate: f32) -> f32 {
+        let coeff = calculate_filter_coefficient(
+            1.0 / (std::f32::consts::TAU * DC_BLOCKER_FREQUENCY),
+            sample_rate,
+        );
+        self.dc_output = input - self.dc_input + coeff * self.dc_output;
+        self.dc_input = input;
+        self.dc_output
+    }
+}
+
+/// The highest oversampling factor that `Upsampler` and `Downsampler` can be set up for.
+pub const MAX_OVERSAMPLING: usize = 8;
+/// The number of nonzero taps on each side of a half-band filter's center tap.
+const HALF_BAND_TAPS: usize = 12;
+/// The nonzero (odd) taps of a 47-tap half-band lowpass, starting from the one closest to the center.
+/// The center tap is always `0.5`, and every other even tap is `0.0`, so those are left out.
+/// This is a Kaiser-windowed sinc (beta = 8), which is flat to about 0.4 times the lower sample rate and down 56 dB by 0.6 times.
+// these were generated with a script, so we keep all of the digits
+#[allow(clippy::excessive_precision)]
+const HALF_BAND_COEFFICIENTS: [f32; HALF_BAND_TAPS] = [
+    0.3160600265,
+    -0.0995336673,
+    0.0532391091,
+    -0.0319059183,
+    0.0195115030,
+    -0.0116852765,
+    0.0066707862,
+    -0.0035394353,
+    0.0016906355,
+    -0.0006899972,
+    0.0002146023,
+    -0.0000323678,
+];
+
+/// One 2x upsampling stage. Because every other tap of a half-band filter is `0.0`,
+/// the polyphase form only needs to run the odd taps for every other output, and the other output is just a delayed input.
+#[derive(Debug, Clone, Copy)]
+struct HalfBandUpsampler {
+    /// The most recent input is at index 0.
+    history: [f32; 2 * HALF_BAND_TAPS],
+}
+impl HalfBandUpsampler {
+    fn process(&mut self, input: f32) -> [f32; 2] {
+        self.history.copy_within(..2 * HALF_BAND_TAPS - 1, 1);
+        self.history[0] = input;
+        // the interpolated sample is halfway between `history[HALF_BAND_TAPS - 1]` and `history[HALF_BAND_TAPS]`
+        // (the factor of 2 makes up for the zeros that upsampling stuffs in between the samples)
+        let interpolated = 2.0
+            * HALF_BAND_COEFFICIENTS
+                .iter()
+                .enumerate()
+                .map(|(j, coefficient)| {
+                    coefficient
+                        * (self.history[HALF_BAND_TAPS - 1 - j] + self.history[HALF_BAND_TAPS + j])
+                })
+                .sum::<f32>();
+        [interpolated, self.history[HALF_BAND_TAPS - 1]]
+    }
+}
+
+/// One 2x downsampling stage, the opposite of `HalfBandUpsampler`.
+/// Only one output is calculated for every 2 inputs, and only the odd taps (plus the center) are needed for it.
+#[derive(Debug, Clone, Copy)]
+struct HalfBandDownsampler {
+    /// The most recent input is at index 0.
+    history: [f32; 4 * HALF_BAND_TAPS],
+}
+impl HalfBandDownsampler {
+    fn process(&mut self, input: [f32; 2]) -> f32 {
+        self.history.copy_within(..4 * HALF_BAND_TAPS - 2, 2);
+        self.history[1] = input[0];
+        self.history[0] = input[1];
+        // the center is an even number of samples back, so it always lands on the samples that `HalfBandUpsampler` didn't interpolate
+        let center = 2 * HALF_BAND_TAPS;
+        0.5 * self.history[center]
+            + HALF_BAND_COEFFICIENTS
+                .iter()
+                .enumerate()
+                .map(|(j, coefficient)| {
+                    coefficient
+                        * (self.history[center - 1 - 2 * j] + self.history[center + 1 + 2 * j])
+                })
+                .sum::<f32>()
+    }
+}
+
+/// How many half-band stages it takes to oversample by `factor`, which should be a power of 2.
+fn oversampling_stages(factor: usize) -> usize {
+    factor.trailing_zeros() as usize
+}
+
+/// Upsamples a signal by 2, 4 or 8 times, with a cascade of polyphase half-band filters.
+pub struct Upsampler {
+    stages: Vec<HalfBandUpsampler>,
+}
+impl Default for Upsampler {
+    fn default() -> Self {
+        Self::new(1)
+    }
+}
+impl Upsampler {
+    /// Construct a new `Upsampler` which can oversample by up to `max_factor` times.
+    /// This allocates, so it should only be called from `initialize()`.
+    pub fn new(max_factor: usize) -> Self {
+        Self {
+            stages: vec![
+                HalfBandUpsampler {
+                    history: [0.0; 2 * HALF_BAND_TAPS]
+                };
+                oversampling_stages(max_factor)
+            ],
+        }
+    }
+    /// Turns a single sample into `output.len()` samples, which has to be a power of 2 (up to `max_factor`).
+    pub fn process(&mut self, input: f32, output: &mut [f32]) {
+        let mut buffer = [0.0; MAX_OVERSAMPLING];
+        output[0] = input;
+        let mut length = 1;
+        for stage in &mut self.stages[..oversampling_stages(output.len())] {
+            for (i, sample) in output[..length].iter().enumerate() {
+                let [first, second] = stage.process(*sample);
+                buffer[2 * i] = first;
+                buffer[2 * i + 1] = second;
+            }
+            length *= 2;
+            output[..length].copy_from_slice(&buffer[..length]);
+        }
+    }
+    /// Clears the filter state, for example when the oversampling factor changes.
+    pub fn reset(&mut self) {
+        for stage in &mut self.stages {
+            stage.history = [0.0; 2 * HALF_BAND_TAPS];
+        }
+    }
+    /// The latency of just the `Upsampler` when oversampling by `factor`, **in samples at the original sample rate**.
+    /// The filters are symmetric, so this is half of the half-band filters' part of `Downsampler::latency()`, and it can be a fraction of a sample.
+    pub fn latency(factor: usize) -> f32 {
+        Downsampler::filter_latency(factor) as f32 / (2 * factor) as f32
+    }
+}
+
+/// Downsamples a signal that was upsampled by an `Upsampler`, back to the original sample rate.
+///
+/// The half-band filters on their own can add up to a fraction of a sample of latency,
+/// so there's a short delay at the high sample rate which rounds it up to a whole number of samples (see `latency()`).
+pub struct Downsampler {
+    stages: Vec<HalfBandDownsampler>,
+    padding: DelayLine,
+}
+impl Default for Downsampler {
+    fn default() -> Self {
+        Self::new(1)
+    }
+}
+impl Downsampler {
+    /// Construct a new `Downsampler` which can work with a signal oversampled by up to `max_factor` times.
+    /// This allocates, so it should only be called from `initialize()`.
+    pub fn new(max_factor: usize) -> Self {
+        Self {
+            stages: vec![
+                HalfBandDownsampler {
+                    history: [0.0; 4 * HALF_BAND_TAPS]
+                };
+                oversampling_stages(max_factor)
+            ],
+            padding: DelayLine::new(max_factor),
+        }
+    }
+    /// Turns `input.len()` samples (a power of 2, up to `max_factor`) back into a single sample.
+    pub fn process(&mut self, input: &[f32]) -> f32 {
+        let factor = input.len();
+        let padding = Self::padding(factor);
+        let mut buffer = [0.0; MAX_OVERSAMPLING];
+        for (buffered, sample) in buffer.iter_mut().zip(input) {
+            *buffered = self.padding.process(*sample, padding);
+        }
+        // the highest sample rate is the last stage of the upsampler, so the stages run in reverse here
+        let mut length = factor;
+        for stage in self.stages[..oversampling_stages(factor)].iter_mut().rev() {
+            length /= 2;
+            for i in 0..length {
+                buffer[i] = stage.process([buffer[2 * i], buffer[2 * i + 1]]);
+            }
+        }
+        buffer[0]
+    }
+    /// Clears the filter state, for example when the oversampling factor changes.
+    pub fn reset(&mut self) {
+        for stage in &mut self.stages {
+            stage.history = [0.0; 4 * HALF_BAND_TAPS];
+        }
+        self.padding.reset();
+    }
+    /// The latency of the half-band filters when upsampling and then downsampling by `factor`, **in samples at the high sample rate**.
+    /// Each stage delays by `2 * HALF_BAND_TAPS - 1` samples at its lower sample rate.
+    fn filter_latency(factor: usize) -> usize {
+        (0..oversampling_stages(factor))
+            .map(|stage| (2 * HALF_BAND_TAPS - 1) * (factor >> stage))
+            .sum()
+    }
+    /// How many samples at the high sample rate it takes to round `filter_latency()` up to a whole sample at the original sample rate.
+    fn padding(factor: usize) -> usize {
+        (factor - Self::filter_latency(factor) % factor) % factor
+    }
+    /// The total latency of an `Upsampler` and a `Downsampler`, **in samples at the original sample rate**.
+    pub fn latency(factor: usize) -> u32 {
+        ((Self::filter_latency(factor) + Self::padding(factor)) / factor) as u32
+    }
+}
+
+/// The attack time **(in seconds)** of the transient shaper's fast envelope.
+const TRANSIENT_FAST_ATTACK: f32 = 0.0005;
+/// The release time **(in seconds)** of the transient shaper's fast envelope.
+const TRANSIENT_FAST_RELEASE: f32 = 0.02;
+/// How far apart **(in decibels)** the fast and slow envelopes have to be for the full emphasis to be applied.
+const TRANSIENT_SENSITIVITY: f32 = 12.0;
+
+/// Everything the `TransientShaper` needs to know. See `CompressorParams` for what each of these does.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TransientShaperSettings {
+    pub enabled: bool,
+    /// **In decibels.**
+    pub attack_emphasis: f32,
+    /// **In decibels.**
+    pub sustain_emphasis: f32,
+}
+impl Default for TransientShaperSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            attack_emphasis: 0.0,
+            sustain_emphasis: 0.0,
+        }
+    }
+}
+
+/// A transient designer, which turns the attacks and the sustain of a sound up or down no matter how loud it is.
+///
+/// It follows the key with two envelopes: a fast one, and a slow one that uses the compressor's attack and release.
+/// When a hit starts, the fast envelope jumps ahead of the slow one, and that's the attack.
+/// As the hit dies away, the fast envelope falls under the slow one, and that's the sustain.
+#[derive(Debug, Default)]
+pub struct TransientShaper {
+    /// Both envelopes are *in linear space*.
+    fast: f32,
+    slow: f32,
+}
+impl TransientShaper {
+    /// Follows a single key sample, and returns a factor to multiply the input signal by.
+    pub fn process(
+        &mut self,
+        key: f32,
+        settings: &TransientShaperSettings,
+        attack_coeff: f32,
+        release_coeff: f32,
+        sample_rate: f32,
+    ) -> f32 {
+        let level = key.abs();
+        let fast_coeff = if level > self.fast {
+            calculate_filter_coefficient(TRANSIENT_FAST_ATTACK, sample_rate)
+        } else {
+            calculate_filter_coefficient(TRANSIENT_FAST_RELEASE, sample_rate)
+        };
+        let slow_coeff = if level > self.slow {
+            attack_coeff
+        } else {
+            release_coeff
+        };
+        self.fast = fast_coeff * self.fast + (1.0 - fast_coeff) * level;
+        self.slow = slow_coeff * self.slow + (1.0 - slow_coeff) * level;
+
+        let difference = gain_to_db_fast(self.fast) - gain_to_db_fast(self.slow);
+        let amount = (difference.abs() / TRANSIENT_SENSITIVITY).min(1.0);
+        let gain_db = if difference > 0.0 {
+            settings.attack_emphasis * amount
+        } else {
+            settings.sustain_emphasis * amount
+        };
+        db_to_gain_fast(gain_db)
+    }
+}
+
+/// Variants represent how the dry and wet signals are blended together.
+#[derive(Enum, Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BlendMode {
+    /// The gains add up to 1. Uncorrelated signals dip by 3 dB in the middle.
+    #[id = "linear"]
+    Linear,
+    /// The powers add up to 1, so the loudness stays about the same across the whole blend.
+    #[id = "equalpower"]
+    #[name = "Equal Power"]
+    EqualPower,
+}
+impl BlendMode {
+    /// Returns the `(dry, wet)` gains for a blend of `mix` (0.0 is fully dry, 1.0 is fully wet).
+    pub fn gains(self, mix: f32) -> (f32, f32) {
+        match self {
+            BlendMode::Linear => (1.0 - mix, mix),
+            BlendMode::EqualPower => {
+                let angle = mix * std::f32::consts::FRAC_PI_2;
+                (angle.cos(), angle.sin())
+            }
+        }
+    }
+}
+
+/// The frequency **(in Hz)** that the wet tilt pivots around.
+pub const WET_TILT_FREQUENCY: f32 = 1000.0;
+
+/// Everything the `ParallelPath` needs to know. See `CompressorParams` for what each of these does.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ParallelSettings {
+    pub blend_mode: BlendMode,
+    pub mix: f32,
+    /// **In decibels.** Positive values turn the highs up and the lows down.
+    pub wet_tilt: f32,
+    /// **In decibels.**
+    pub wet_gain: f32,
+}
+impl Default for ParallelSettings {
+    fn default() -> Self {
+        Self {
+            blend_mode: BlendMode::Linear,
+            mix: 1.0,
+            wet_tilt: 0.0,
+            wet_gain: 0.0,
+        }
+    }
+}
+
+/// The processing that only the wet signal gets before it's blended with the dry one, for parallel compression.
+///
+/// The tilt is built from a `LinkwitzRileyCrossover` rather than a shelf, and the dry signal is run through a crossover of its own.
+/// Both paths get the exact same phase shift, so the blend never combs, no matter where the tilt is.
+///
+/// The crossovers always run, even with no tilt. Skipping them would mean fading between the allpassed signal and the plain one
+/// whenever the tilt moves away from 0, and that blend combs. They're allpasses, so this only shifts the phase, the response stays flat.
+#[derive(Debug, Default)]
+pub struct ParallelPath {
+    wet_tilt: LinkwitzRileyCrossover,
+    dry_allpass: LinkwitzRileyCrossover,
+}
+impl ParallelPath {
+    /// Processes a single sample of both paths, and returns the `(dry, wet)` samples, ready to be blended.
+    /// They should already be lined up with each other (the compressor's dry output already has the lookahead delay).
+    pub fn process(
+        &mut self,
+        dry: f32,
+        wet: f32,
+        settings: &ParallelSettings,
+        sample_rate: f32,
+    ) -> (f32, f32) {
+        self.wet_tilt.set_frequency(WET_TILT_FREQUENCY, sample_rate);
+        self.dry_allpass
+            .set_frequency(WET_TILT_FREQUENCY, sample_rate);
+        let (dry_low, dry_high) = self.dry_allpass.process(dry);
+        let (wet_low, wet_high) = self.wet_tilt.process(wet);
+        let low_gain = db_to_gain_fast(settings.wet_gain - 0.5 * settings.wet_tilt);
+        let high_gain = db_to_gain_fast(settings.wet_gain + 0.5 * settings.wet_tilt);
+        (
+            dry_low + dry_high,
+            wet_low * low_gain + wet_high * high_gain,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    /// Calculates the RMS of the last `length` samples the slow way.
+    /// Anything from before the start of `input` counts as silence.
+    fn brute_force_rms(input: &[f32], length: usize) -> f32 {
+        let start = input.len().saturating_sub(length);
+        let squared_sum: f64 = input[start..].iter().map(|x| (*x as f64).powi(2)).sum();
+        (squared_sum / length as f64).sqrt() as f32
+    }
+
+    #[test]
+    fn rms_resizing_while_processing() {
+        let max_length = 1440;
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut detector = RmsLevelDetector::new(max_length);
+        let mut input = Vec::new();
+
+        for i in 0..50_000 {
+            // sweep the window around, kind of like someone dragging the RMS knob
+            if i % 64 == 0 {
+                detector.set_length(rng.gen_range(1..=max_length));
+            }
+            let sample = rng.gen_range(-1.0..1.0);
+            input.push(sample);
+
+            let rms = detector.calculate_level(sample);
+            let expected = brute_force_rms(&input, detector.length);
+            assert!(
+                (rms - expected).abs() < 1e-3,
+                "sample {i}: got {rms}, expected {expected}"
+            );
+        }
+    }
+
+    #[test]
+    fn rms_length_is_clamped() {
+        let mut detector = RmsLevelDetector::new(100);
+
+        detector.set_length(0);
+        assert_eq!(detector.length, 1);
+        detector.set_length(1000);
+        assert_eq!(detector.length, 100);
+        // resizing should never touch the underlying buffer
+        assert_eq!(detector.history.max_delay(), 100);
+    }
+
+    #[test]
+    fn rms_growing_window_uses_real_history() {
+        let mut detector = RmsLevelDetector::new(8);
+        detector.set_length(2);
+        for _ in 0..8 {
+            detector.calculate_level(1.0);
+        }
+        // the whole history is full of 1s, so growing the window shouldn't pull in any silence
+        detector.set_length(8);
+        assert_eq!(detector.calculate_level(1.0), 1.0);
+    }
+    /// Runs an RMS detector over a long stretch of simulated program material, checking it against a brute-force RMS as it goes.
+    /// The material switches between loud noise, quiet noise and digital silence, since going from loud to silent is
+    /// exactly where a drifting running sum would show up.
+    fn simulate_session(hours: u64, sample_rate: u64) {
+        let window = 480;
+        let mut detector = RmsLevelDetector::new(window);
+        // we keep our own copy of the window for the brute-force RMS
+        let mut recent = vec![0.0f32; window];
+
+        // a tiny xorshift generator, since we need a *lot* of samples and this test should still finish quickly
+        let mut state: u32 = 0x1234_5678;
+        let mut noise = move || {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state as f32 / u32::MAX as f32 * 2.0 - 1.0
+        };
+
+        let total_samples = hours * 60 * 60 * sample_rate;
+        // every section of material is 1.5 seconds long, and we check the result once per section
+        let section_length = sample_rate * 3 / 2;
+        for i in 0..total_samples {
+            let section = i / section_length;
+            let sample = match section % 3 {
+                0 => noise(),
+                1 => noise() * 0.001,
+                _ => 0.0,
+            };
+            recent[i as usize % window] = sample;
+            let rms = detector.calculate_level(sample);
+
+            if i % section_length == section_length - 1 {
+                let squared_sum: f64 = recent.iter().map(|x| (*x as f64).powi(2)).sum();
+                let expected = (squared_sum / window as f64).sqrt() as f32;
+                assert!(
+                    (rms - expected).abs() <= expected * 1e-5,
+                    "sample {i}: got {rms}, expected {expected}"
+                );
+                // silence should be *exactly* silent, not "close enough"
+                if section % 3 == 2 {
+                    assert_eq!(rms, 0.0);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn rms_long_session_matches_brute_force() {
+        // drift depends on the number of samples, not the sample rate,
+        // so an hour at a low sample rate keeps this reasonably fast while still being tens of millions of samples
+        simulate_session(1, 8_000);
+    }
+
+    #[test]
+    #[ignore = "slow in debug builds, run with `cargo test --release -- --ignored`"]
+    fn rms_broadcast_session_matches_brute_force() {
+        // a full 8 hour session at 48 kHz
+        simulate_session(8, 48_000);
+    }
+
+    #[test]
+    fn transfer_curve_is_continuous() {
+        for knee_width in [0.0, 5.0, 20.0] {
+            // the threshold lands exactly on one of the points
+            let dynamics = DynamicsCurve {
+                threshold: -10.0,
+                ratio: 4.0,
+                knee_width,
+                ..Default::default()
+            };
+            let curve: Vec<_> =
+                transfer_curve(dynamics, Character::Vca, -100.0, 0.0, 101).collect();
+            assert_eq!(curve.len(), 101);
+            assert_eq!(curve[0], (-100.0, -100.0));
+            assert_eq!(curve[100], (0.0, -7.5));
+
+            for window in curve.windows(2) {
+                let ((_, previous), (input_db, output_db)) = (window[0], window[1]);
+                assert!(
+                    output_db.is_finite(),
+                    "knee {knee_width}: NaN at {input_db} dB"
+                );
+                // with a 4:1 ratio, 1 dB in should never be more than 1 dB out, or less than 0.25 dB out
+                let slope = output_db - previous;
+                assert!(
+                    (0.25 - 1e-4..=1.0 + 1e-4).contains(&slope),
+                    "knee {knee_width}: slope of {slope} at {input_db} dB"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn band_splitter_sums_flat() {
+        let sample_rate = 48_000.0;
+        for num_bands in 1..=MAX_BANDS {
+            for frequency in [30.0, 120.0, 500.0, 1000.0, 3000.0, 6000.0, 15_000.0] {
+                let mut splitter = BandSplitter::default();
+                splitter.set_frequencies(&[120.0, 1000.0, 6000.0], sample_rate);
+
+                // skip the first second so the filters can settle, then compare the RMS
+                // (the phase is shifted, so the peaks land in between different samples)
+                let mut input_sum = 0.0f32;
+                let mut output_sum = 0.0f32;
+                for i in 0..(sample_rate as usize * 2) {
+                    let input = (std::f32::consts::TAU * frequency * i as f32 / sample_rate).sin();
+                    let output: f32 = splitter.process(input, num_bands).iter().sum();
+                    if i >= sample_rate as usize {
+                        input_sum += input.powi(2);
+                        output_sum += output.powi(2);
+                    }
+                }
+                let difference_db = gain_to_db_fast((output_sum / input_sum).sqrt());
+                assert!(
+                    difference_db.abs() < 0.1,
+                    "{num_bands} bands: {difference_db} dB off at {frequency} Hz"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn dynamics_curve_ranges() {
+        let curve = DynamicsCurve {
+            threshold: -40.0,
+            ratio: 1.0,
+            knee_width: 0.0,
+            ..Default::default()
+        };
+
+        // a gate: 20 dB below the threshold would be 2000 dB down, but the range stops it at 30
+        let gate = DynamicsCurve {
+            expansion_ratio: 100.0,
+            expansion_range: 30.0,
+            ..curve
+        };
+        assert_eq!(gate.output_db(-60.0), -90.0);
+        assert_eq!(gate.output_db(-30.0), -30.0);
+
+        // 2:1 upward compression would bring -60 up by 10 dB, and -80 up by 20 (but that's past the range)
+        let upward = DynamicsCurve {
+            upward_compression_ratio: 2.0,
+            upward_compression_range: 15.0,
+            ..curve
+        };
+        assert_eq!(upward.output_db(-60.0), -50.0);
+        assert_eq!(upward.output_db(-80.0), -65.0);
+        assert_eq!(upward.output_db(-30.0), -30.0);
+
+        // 1:2 upward expansion above the threshold
+        let expander = DynamicsCurve {
+            upward_expansion_ratio: 2.0,
+            upward_expansion_range: 15.0,
+            ..curve
+        };
+        assert_eq!(expander.output_db(-35.0), -30.0);
+        assert_eq!(expander.output_db(-10.0), 5.0);
+        assert_eq!(expander.output_db(-50.0), -50.0);
+
+        // 20:1 would take 38 dB off a 0 dB input, but the range only allows 6
+        let limited = DynamicsCurve {
+            ratio: 20.0,
+            range: 6.0,
+            ..curve
+        };
+        assert_eq!(limited.output_db(0.0), -6.0);
+        // (below the range, it's just a regular compressor)
+        assert!((limited.output_db(-39.0) + 39.95).abs() < 1e-4);
+        // ...and the top of the range (the default) doesn't limit anything, even past 60 dB of gain reduction
+        let unlimited = DynamicsCurve {
+            ratio: 20.0,
+            range: MAX_RANGE,
+            ..curve
+        };
+        assert_eq!(DynamicsCurve::default().range, MAX_RANGE);
+        assert!((unlimited.output_db(40.0) + 36.0).abs() < 1e-4);
+
+        // silence shouldn't turn into NaN, whatever the ratios are
+        assert!(gate.output_db(f32::NEG_INFINITY) == f32::NEG_INFINITY);
+        assert!(DynamicsCurve::default().output_db(f32::NEG_INFINITY) == f32::NEG_INFINITY);
+    }
+
+    #[test]
+    fn linked_levels_match_identical_channels() {
+        // with the same signal on both channels, linking them shouldn't change anything, whatever the detector
+        let sample_rate = 48_000.0;
+        for level_detection_type in [
+            LevelDetectionType::Peak,
+            LevelDetectionType::Rms,
+            LevelDetectionType::TruePeak,
+        ] {
+            let independent = CompressorSettings {
+                level_detection_type,
+                ..Default::default()
+            };
+            let linked = CompressorSettings {
+                rms_mix: 1.0,
+                ..independent
+            };
+            let mut reference = Compressor::new();
+            reference.detectors.rms = RmsLevelDetector::new(480);
+            let mut stereo = [Compressor::new(), Compressor::new()];
+            for compressor in &mut stereo {
+                compressor.detectors.rms = RmsLevelDetector::new(480);
+            }
+
+            for i in 0..4_800 {
+                let sample = (i as f32 * 0.3).sin();
+                let level = reference.detect(sample, &independent, sample_rate);
+                let (_, _, expected) = reference.process(sample, level, &independent, sample_rate);
+
+                let levels = stereo
+                    .each_mut()
+                    .map(|compressor| compressor.detect(sample, &linked, sample_rate));
+                let shared_level = link_levels(&levels);
+                for compressor in &mut stereo {
+                    let (_, _, gain_reduction) =
+                        compressor.process(sample, shared_level, &linked, sample_rate);
+                    assert_eq!(gain_reduction, expected);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn hold_delays_release() {
+        let sample_rate = 48_000.0;
+        let settings = CompressorSettings {
+            level_detection_type: LevelDetectionType::Peak,
+            hold_time: 0.01,
+            ..Default::default()
+        };
+        let mut compressor = Compressor::new();
+        let process = |compressor: &mut Compressor, sample: f32| {
+            let level = compressor.detect(sample, &settings, sample_rate);
+            compressor.process(sample, level, &settings, sample_rate)
+        };
+
+        for _ in 0..4_800 {
+            process(&mut compressor, 1.0);
+        }
+        let held = compressor.average_gain();
+        // the signal stops, but the level shouldn't move until the hold time is over...
+        for _ in 0..400 {
+            process(&mut compressor, 0.0);
+        }
+        assert_eq!(compressor.average_gain(), held);
+        // ...and then it releases like normal
+        for _ in 0..400 {
+            process(&mut compressor, 0.0);
+        }
+        assert!(compressor.average_gain() < held);
+    }
+
+    #[test]
+    fn feedback_reduces_less_than_feed_forward() {
+        let sample_rate = 48_000.0;
+        let gain_reduction = |topology| {
+            let settings = CompressorSettings {
+                topology,
+                level_detection_type: LevelDetectionType::Peak,
+                ..Default::default()
+            };
+            let mut compressor = Compressor::new();
+            let mut gain_reduction = 1.0;
+            for _ in 0..48_000 {
+                let level = compressor.detect(0.5, &settings, sample_rate);
+                gain_reduction = compressor.process(0.5, level, &settings, sample_rate).2;
+            }
+            gain_reduction
+        };
+
+        let feed_forward = gain_reduction(Topology::FeedForward);
+        let feedback = gain_reduction(Topology::Feedback);
+        assert!(feed_forward < 1.0);
+        // the detector hears the already reduced level, so it backs off
+        assert!(feedback > feed_forward, "{feedback} vs {feed_forward}");
+        assert!(feedback < 1.0);
+    }
+
+    /// Runs a compressor on `input` (with the key being the input itself), and returns the gain reduction **in decibels** for every sample.
+    fn gain_reduction_db(settings: &CompressorSettings, input: &[f32]) -> Vec<f32> {
+        let sample_rate = 48_000.0;
+        let mut compressor = Compressor::new();
+        input
+            .iter()
+            .map(|sample| {
+                let level = compressor.detect(*sample, settings, sample_rate);
+                let (_, _, gain_reduction) =
+                    compressor.process(*sample, level, settings, sample_rate);
+                -gain_to_db_fast(gain_reduction)
+            })
+            .collect()
+    }
+
+    /// Settings where a full-scale input gets exactly 15 dB of gain reduction, with no knee to get in the way.
+    fn envelope_settings(envelope_type: EnvelopeType) -> CompressorSettings {
+        CompressorSettings {
+            curve: DynamicsCurve {
+                threshold: -20.0,
+                ratio: 4.0,
+                knee_width: 0.0,
+                ..Default::default()
+            },
+            level_detection_type: LevelDetectionType::Peak,
+            envelope_type,
+            attack_time: 0.001,
+            release_time: 0.05,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn log_envelopes_follow_their_time_constants() {
+        // 1 second of full scale and then 1 second of silence
+        let input: Vec<f32> = (0..96_000)
+            .map(|i| if i < 48_000 { 1.0 } else { 0.0 })
+            .collect();
+        let full = 15.0;
+        for envelope_type in [EnvelopeType::SmoothBranching, EnvelopeType::SmoothDecoupled] {
+            let reductions = gain_reduction_db(&envelope_settings(envelope_type), &input);
+            // one time constant in, a one-pole filter has gone 1 - 1/e of the way...
+            let attacked = reductions[48 - 1];
+            assert!(
+                (attacked - full * (1.0 - (-1.0f32).exp())).abs() < 0.2,
+                "{envelope_type:?}: {attacked}"
+            );
+            assert!((reductions[48_000 - 1] - full).abs() < 1e-3);
+            // ...and one time constant after the level drops, it has 1/e of the way left to go
+            let released = reductions[48_000 + 2_400 - 1];
+            assert!(
+                (released - full * (-1.0f32).exp()).abs() < 0.2,
+                "{envelope_type:?}: {released}"
+            );
+        }
+    }
+
+    #[test]
+    fn decoupled_envelope_doesnt_overshoot() {
+        let mut rng = StdRng::seed_from_u64(7);
+        // bursts of different lengths and levels, so the attack and release keep interrupting each other
+        let mut input = Vec::new();
+        for _ in 0..50 {
+            let level = if rng.gen_bool(0.5) {
+                rng.gen_range(0.1..1.0)
+            } else {
+                0.0
+            };
+            let length = rng.gen_range(1..2_000);
+            input.resize(input.len() + length, level);
+        }
+        let reductions =
+            gain_reduction_db(&envelope_settings(EnvelopeType::SmoothDecoupled), &input);
+        for (i, reduction) in reductions.iter().enumerate() {
+            // never more than the loudest possible input asks for, and never negative
+            assert!(*reduction <= 15.0 + 1e-3, "sample {i}: {reduction}");
+            assert!(*reduction >= -1e-3, "sample {i}: {reduction}");
+        }
+    }
+
+    #[test]
+    fn level_envelope_is_unchanged() {
+        let settings = envelope_settings(EnvelopeType::Level);
+        let sample_rate = 48_000.0;
+        let attack_coeff = calculate_filter_coefficient(settings.attack_time, sample_rate);
+        let release_coeff = calculate_filter_coefficient(settings.release_time, sample_rate);
+        let mut rng = StdRng::seed_from_u64(7);
+        let input: Vec<f32> = (0..48_000).map(|_| rng.gen_range(-1.0..1.0)).collect();
+
+        // the original design: a branching one-pole filter on the linear level, and then the gain computer
+        let mut average = 0.0;
+        let expected = input.iter().map(|sample| {
+            let level = sample.abs();
+            let theta = if level > average {
+                attack_coeff
+            } else {
+                release_coeff
+            };
+            average = (1.0 - theta) * level + theta * average;
+            let input_db = gain_to_db_fast(average);
+            -gain_to_db_fast(db_to_gain_fast(
+                settings.curve.output_db(input_db) - input_db,
+            ))
+        });
+        for (i, (reduction, expected)) in gain_reduction_db(&settings, &input)
+            .iter()
+            .zip(expected)
+            .enumerate()
+        {
+            assert!(
+                (reduction - expected).abs() < 1e-4,
+                "sample {i}: {reduction} vs {expected}"
+            );
+        }
+    }
+
+    #[test]
+    fn static_makeup_undoes_the_curve() {
+        let compressing = CompressorSettings {
+            curve: DynamicsCurve {
+                threshold: -20.0,
+                ratio: 4.0,
+                knee_width: 0.0,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert_eq!(compressing.static_makeup_db(), 15.0);
+        // a curve that boosts a full-scale input gets turned back down
+        let expanding = CompressorSettings {
+            curve: DynamicsCurve {
+                ratio: 1.0,
+                upward_expansion_ratio: 2.0,
+                ..compressing.curve
+            },
+            ..compressing
+        };
+        // (the 20 dB of boost is capped by the upward range)
+        assert_eq!(
+            expanding.static_makeup_db(),
+            -expanding.curve.upward_expansion_range
+        );
+    }
+
+    #[test]
+    fn multiband_static_makeup_skips_bypassed_bands() {
+        let band = |threshold: f32, bypass: bool| BandSettings {
+            compressor: CompressorSettings {
+                curve: DynamicsCurve {
+                    threshold,
+                    ratio: 2.0,
+                    knee_width: 0.0,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            solo: false,
+            bypass,
+        };
+        let mut settings = MultibandSettings {
+            num_bands: 3,
+            crossovers: [120.0, 1000.0, 6000.0],
+            bands: [
+                band(-20.0, false),
+                band(-40.0, true),
+                band(-10.0, false),
+                // not one of the bands in use
+                band(-60.0, false),
+            ],
+        };
+        // (10 dB + 5 dB) / 2, the bypassed band doesn't count
+        assert_eq!(settings.static_makeup_db(), 7.5);
+        for band in &mut settings.bands {
+            band.bypass = true;
+        }
+        assert_eq!(settings.static_makeup_db(), 0.0);
+    }
+
+    #[test]
+    fn character_models() {
+        let curve = DynamicsCurve {
+            threshold: -20.0,
+            ratio: 8.0,
+            knee_width: 0.0,
+            ..Default::default()
+        };
+        let mut models = CharacterModels::default();
+
+        // the vari-mu ratio rises with the level, but never past the setting
+        let vari_mu = models.get_mut(Character::VariMu);
+        let slope = |model: &mut dyn CharacterModel, input_db: f32| {
+            model.output_db(&curve, input_db + 1.0) - model.output_db(&curve, input_db)
+        };
+        let (low, high) = (slope(vari_mu, -15.0), slope(vari_mu, 10.0));
+        assert!(low > high);
+        assert!(high > curve.ratio.recip());
+        assert_eq!(vari_mu.output_db(&curve, -30.0), -30.0);
+        // the static makeup follows the character's curve, not the plain one
+        let settings = CompressorSettings {
+            curve,
+            character: Character::VariMu,
+            ..Default::default()
+        };
+        assert_eq!(settings.static_makeup_db(), -vari_mu.output_db(&curve, 0.0));
+        assert!(settings.static_makeup_db() < curve.makeup_db());
+
+        // the opto release slows down once most of the gain reduction has recovered
+        let release = calculate_filter_coefficient(0.1, 48_000.0);
+        let opto = models.get_mut(Character::Opto);
+        for _ in 0..96_000 {
+            opto.release_coeff(release, 10.0, 48_000.0);
+        }
+        assert_eq!(opto.release_coeff(release, 9.0, 48_000.0), release);
+        assert!(opto.release_coeff(release, 2.0, 48_000.0) > release);
+    }
+
+    #[test]
+    fn parallel_path_stays_in_phase() {
+        let sample_rate = 48_000.0;
+        let flat = ParallelSettings {
+            wet_tilt: 0.0,
+            wet_gain: 6.0,
+            ..Default::default()
+        };
+        let tilted = ParallelSettings {
+            wet_tilt: 6.0,
+            ..flat
+        };
+        let mut path = ParallelPath::default();
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut check_in_phase = |path: &mut ParallelPath| {
+            // with no tilt, the wet signal is just the dry signal with the wet gain, sample for sample
+            for _ in 0..4_800 {
+                let sample = rng.gen_range(-1.0..1.0);
+                let (dry, wet) = path.process(sample, sample, &flat, sample_rate);
+                assert!((wet - dry * db_to_gain_fast(6.0)).abs() < 1e-4);
+            }
+        };
+
+        check_in_phase(&mut path);
+        // moving the tilt away and back doesn't leave the paths out of step
+        for i in 0..4_800 {
+            let settings = if i < 2_400 { tilted } else { flat };
+            path.process(1.0, 1.0, &settings, sample_rate);
+        }
+        check_in_phase(&mut path);
+
+        // the dry path is an allpass, so a sine at the tilt frequency (where the phase shift is the largest) keeps its level
+        let mut path = ParallelPath::default();
+        let sine: Vec<f32> = (0..48_000)
+            .map(|i| (i as f32 * std::f32::consts::TAU * WET_TILT_FREQUENCY / sample_rate).sin())
+            .collect();
+        let dry: Vec<f32> = sine
+            .iter()
+            .map(|sample| path.process(*sample, *sample, &flat, sample_rate).0)
+            .collect();
+        let peak = dry[24_000..]
+            .iter()
+            .fold(0.0, |peak: f32, x| peak.max(x.abs()));
+        assert!((peak - 1.0).abs() < 1e-2, "{peak}");
+
+        for mix in [0.0, 0.3, 0.5, 1.0] {
+            let (dry, wet) = BlendMode::EqualPower.gains(mix);
+            assert!((dry * dry + wet * wet - 1.0).abs() < 1e-6);
+            let (dry, wet) = BlendMode::Linear.gains(mix);
+            assert_eq!(dry + wet, 1.0);
+        }
+    }
+
+    #[test]
+    fn transient_shaper_emphasis() {
+        let sample_rate = 48_000.0;
+        let settings = TransientShaperSettings {
+            enabled: true,
+            attack_emphasis: 6.0,
+            sustain_emphasis: -6.0,
+        };
+        let attack = calculate_filter_coefficient(0.02, sample_rate);
+        let release = calculate_filter_coefficient(0.5, sample_rate);
+        let mut shaper = TransientShaper::default();
+
+        // the start of a hit is turned up...
+        let mut gains =
+            (0..240).map(|_| shaper.process(1.0, &settings, attack, release, sample_rate));
+        assert!(gains.nth(100).unwrap() > 1.0);
+        // ...a steady signal is left alone once both envelopes have caught up...
+        for _ in 0..48_000 {
+            shaper.process(1.0, &settings, attack, release, sample_rate);
+        }
+        let steady = shaper.process(1.0, &settings, attack, release, sample_rate);
+        assert!((steady - 1.0).abs() < 1e-3);
+        // ...and the tail is turned down
+        for _ in 0..4_800 {
+            shaper.process(0.1, &settings, attack, release, sample_rate);
+        }
+        assert!(shaper.process(0.1, &settings, attack, release, sample_rate) < 1.0);
+    }
+
+    #[test]
+    fn crest_factor_scales_release() {
+        let coeff = calculate_filter_coefficient(AUTO_RELEASE_CREST_TIME, 48_000.0);
+
+        // a steady sine has a crest factor of 3 dB, so the release should get longer
+        let mut sine = CrestFactorDetector::default();
+        for i in 0..48_000 {
+            sine.process((i as f32 * 0.1).sin(), coeff);
+        }
+        assert!(sine.release_scale() > 1.0);
+
+        // a click every 100 ms is about as spiky as it gets, so the release should get shorter
+        let mut clicks = CrestFactorDetector::default();
+        for i in 0..48_000 {
+            clicks.process(if i % 4_800 == 0 { 1.0 } else { 0.0 }, coeff);
+        }
+        assert!(clicks.release_scale() < 1.0);
+
+        // the compressor keeps measuring while the auto release is off, so turning it on doesn't start from scratch
+        let settings = CompressorSettings {
+            auto_release: 0.0,
+            ..Default::default()
+        };
+        let mut compressor = Compressor::new();
+        for i in 0..48_000 {
+            let key = if i % 4_800 == 0 { 1.0 } else { 0.0 };
+            let level = compressor.detect(key, &settings, 48_000.0);
+            compressor.process(key, level, &settings, 48_000.0);
+        }
+        assert_eq!(
+            compressor.crest_factor.release_scale(),
+            clicks.release_scale()
+        );
+    }
+
+    #[test]
+    fn sidechain_bandpass_keeps_only_the_band() {
+        let sample_rate = 48_000.0;
+        let settings = SidechainFilterSettings {
+            filter_type: SidechainFilterType::BandPass,
+            frequency: 1_000.0,
+            q: 2.0,
+        };
+        let peak = |frequency: f32| {
+            let mut filter = SidechainFilter::default();
+            (0..48_000)
+                .map(|i| (std::f32::consts::TAU * frequency * i as f32 / sample_rate).sin())
+                .map(|sample| filter.process(sample, &settings, sample_rate))
+                .skip(24_000)
+                .fold(0.0f32, |peak, sample| peak.max(sample.abs()))
+        };
+
+        assert!((peak(1_000.0) - 1.0).abs() < 0.01);
+        assert!(peak(100.0) < 0.1);
+        assert!(peak(10_000.0) < 0.1);
+    }
+
+    #[test]
+    fn split_band_de_esser_only_touches_the_band() {
+        let sample_rate = 48_000.0;
+        let peak = |frequency: f32, gain_reduction: f32| {
+            let mut de_esser = SplitBandDeEsser::default();
+            (0..48_000)
+                .map(|i| (std::f32::consts::TAU * frequency * i as f32 / sample_rate).sin())
+                .map(|sample| de_esser.process(sample, gain_reduction, 6_500.0, sample_rate))
+                .skip(24_000)
+                .fold(0.0f32, |peak, sample| peak.max(sample.abs()))
+        };
+
+        // without any gain reduction, the band and the notch add back up to the input
+        assert!((peak(6_500.0, 1.0) - 1.0).abs() < 0.01);
+        assert!((peak(6_500.0, 0.5) - 0.5).abs() < 0.01);
+        assert!(peak(200.0, 0.5) > 0.95);
+    }
+
+    #[test]
+    fn oversampling_round_trip_matches_latency() {
+        for factor in [1, 2, 4, 8] {
+            let mut upsampler = Upsampler::new(MAX_OVERSAMPLING);
+            let mut downsampler = Downsampler::new(MAX_OVERSAMPLING);
+            let latency = Downsampler::latency(factor) as usize;
+
+            // a 1 kHz sine at 48 kHz is well inside the passband, so it should come out the same, just delayed
+            let input: Vec<f32> = (0..4_800)
+                .map(|i| (std::f32::consts::TAU * 1_000.0 * i as f32 / 48_000.0).sin())
+                .collect();
+            let output: Vec<f32> = input
+                .iter()
+                .map(|sample| {
+                    let mut upsampled = [0.0; MAX_OVERSAMPLING];
+                    upsampler.process(*sample, &mut upsampled[..factor]);
+                    downsampler.process(&upsampled[..factor])
+                })
+                .collect();
+
+            for (output, input) in output[latency..].iter().zip(&input).skip(200) {
+                assert!((output - input).abs() < 0.01, "factor {factor}");
+            }
+        }
+    }
+
+    #[test]
+    fn saturation_only_shapes_loud_signals() {
+        for saturation_type in [
+            SaturationType::Tape,
+            SaturationType::Tube,
+            SaturationType::HardClip,
+        ] {
+            let settings = SaturationSettings {
+                position: SaturationPosition::Post,
+                saturation_type,
+                drive: 12.0,
+                mix: 1.0,
+            };
+            let peak = |amplitude: f32| {
+                let mut saturator = Saturator::default();
+                (0..48_000)
+                    .map(|i| {
+                        amplitude * (std::f32::consts::TAU * 100.0 * i as f32 / 48_000.0).sin()
+                    })
+                    .map(|sample| saturator.process(sample, &settings, 48_000.0))
+                    .skip(24_000)
+                    .fold(0.0f32, |peak, sample| peak.max(sample.abs()))
+            };
+
+            // -60 dB is way below where any of the curves start bending
+            assert!(
+                (peak(0.001) / 0.001 - 1.0).abs() < 0.05,
+                "{saturation_type:?}"
+            );
+            // a full-scale sine driven by 12 dB definitely gets squashed
+            assert!(peak(1.0) < 0.5, "{saturation_type:?}");
+        }
+    }
+
+    #[test]
+    fn limiter_never_goes_over_ceiling() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let lookahead = 64;
+        let ceiling = db_to_gain_fast(-1.0);
+        let release_coeff = calculate_filter_coefficient(0.05, 48_000.0);
+        let mut limiter = Limiter::new(lookahead);
+        let latency = limiter.latency() as usize;
+        // the output is oversampled again, to check the peaks in between samples too
+        let mut output_detectors: [TruePeakLevelDetector; 2] = Default::default();
+
+        let mut input = Vec::new();
+        for i in 0..50_000 {
+            // random loud bursts, some of them way over the ceiling
+            let level = if (i / 1000) % 2 == 0 { 4.0 } else { 0.5 };
+            let frame = [rng.gen_range(-level..level), rng.gen_range(-level..level)];
+            input.push(frame);
+
+            let mut output = frame;
+            let gain = limiter.process(&mut output, ceiling, release_coeff);
+            assert!(
+                output.iter().all(|sample| sample.abs() <= ceiling),
+                "sample {i}: {output:?} is over the ceiling"
+            );
+            for (sample, detector) in output.iter().zip(&mut output_detectors) {
+                let true_peak = detector.calculate_level(*sample);
+                assert!(
+                    true_peak <= ceiling * 1.001,
+                    "sample {i}: the true peak {true_peak} is over the ceiling"
+                );
+            }
+            // the gain should really be applied to the delayed input, not just clipped
+            if i >= latency {
+                let delayed = input[i - latency];
+                for (out, delayed) in output.iter().zip(delayed) {
+                    assert!((out - delayed * gain).abs() < 1e-6, "sample {i}");
+                }
+            }
+        }
+    }
+}
@@ -1,4 +1,7 @@
-use std::{mem::discriminant, sync::atomic::Ordering};
+use std::{
+    mem::discriminant,
+    sync::{atomic::Ordering, Mutex},
+};
 
 use nih_plug::nih_log;
 use nih_plug_webview::{
@@ -7,9 +10,11 @@ use nih_plug_webview::{
 use serde_json::json;
 
 use crate::{
+    dsp::{Character, DynamicsCurve},
     params::{
         Amplitude, Message,
         Parameter::{self, *},
+        TransferCurve,
     },
     CompressorPlugin,
 };
@@ -21,6 +26,9 @@ pub fn create_editor(plugin: &CompressorPlugin) -> WebViewEditor {
     let pre_amplitude = plugin.pre_amplitude.clone();
     let post_amplitude = plugin.post_amplitude.clone();
     let reduced = plugin.amt_reduced.clone();
+    let band_reduced = plugin.band_reduced.clone();
+    // the curve (and character) that the GUI's transfer curve was last calculated with
+    let sent_curve: Mutex<Option<(DynamicsCurve, Character)>> = Mutex::new(None);
 
     let size = (750, 500);
 
@@ -73,6 +81,7 @@ pub fn create_editor(plugin: &CompressorPlugin) -> WebViewEditor {
                     match action {
                         Message::Init => {
                             nih_log!("GUI Opened, sending initial data..");
+                            *sent_curve.lock().unwrap() = None;
                             // TODO:
                             // is there a nicer way ot do this?
                             let vec = vec![
@@ -81,12 +90,19 @@ pub fn create_editor(plugin: &CompressorPlugin) -> WebViewEditor {
                                 KneeWidth(params.knee_width.value()),
                                 AttackTime(params.attack_time.value()),
                                 ReleaseTime(params.release_time.value()),
+                                HoldTime(params.hold_time.value()),
                                 InputGain(params.input_gain.value()),
                                 OutputGain(params.output_gain.value()),
                                 DryWet(params.dry_wet.value()),
                                 RmsBufferSize(params.rms_buffer_size.value()),
                                 Lookahead(params.lookahead.value()),
-                                RmsMix(params.lookahead.value()),
+                                RmsMix(params.rms_mix.value()),
+                                Range(params.range.value()),
+                                DeEsserFrequency(params.de_esser_frequency.value()),
+                                DeEsserListen(params.de_esser_listen.value()),
+                                TransientShaper(params.transient_shaper.value()),
+                                AttackEmphasis(params.attack_emphasis.value()),
+                                SustainEmphasis(params.sustain_emphasis.value()),
                             ];
 
                             for v in vec {
@@ -94,19 +110,26 @@ pub fn create_editor(plugin: &CompressorPlugin) -> WebViewEditor {
                             }
                         }
                         Message::ParameterUpdate(event) => {
-                            let (param, value) = params.get_param(&event);
+                            if let Some((param, value)) = params.get_param(&event) {
+                                setter.begin_set_parameter(param);
+                                setter.set_parameter(param, value);
+                                setter.end_set_parameter(param);
+                            } else if let Some((param, value)) = params.get_bool_param(&event) {
+                                setter.begin_set_parameter(param);
+                                setter.set_parameter(param, value);
+                                setter.end_set_parameter(param);
+                            }
                             // todo(?)
                             // is retain() necessary
                             gui_event_buffer.retain(|d| discriminant(d) != discriminant(&event));
                             gui_event_buffer.push(event);
-
-                            setter.begin_set_parameter(param);
-                            setter.set_parameter(param, value);
-                            setter.end_set_parameter(param);
                         }
                         Message::WindowClosed => println!("Window closed"),
-                        // we should not ever receive these from the frontend. they are backend only
-                        Message::Amplitude(_) => todo!(),
+                        // we should not ever receive these from the frontend. they are backend only,
+                        // so a (malformed) one from the webview is ignored instead of taking down the editor
+                        Message::Amplitude(_) | Message::TransferCurve(_) => {
+                            nih_log!("Ignoring a backend-only message from the GUI")
+                        }
                     }
                 } else {
                     println!("Error receiving message from GUI");
@@ -128,10 +151,30 @@ pub fn create_editor(plugin: &CompressorPlugin) -> WebViewEditor {
                     .expect("Error sending data to frontend");
             }
 
+            // the curve comes straight from the DSP's gain computer, so it only needs to be resent when it changes
+            let curve = (params.dynamics_curve(), params.character.value());
+            let mut sent_curve = sent_curve.lock().unwrap();
+            if *sent_curve != Some(curve) {
+                let message = TransferCurve::new(curve.0, curve.1);
+                ctx.send_json(json!(message))
+                    .expect("Error sending data to frontend");
+                *sent_curve = Some(curve);
+            }
+
             let pre = pre_amplitude.load(Ordering::Relaxed);
             let post = post_amplitude.load(Ordering::Relaxed);
             let gr = reduced.load(Ordering::Relaxed);
-            let message = Amplitude::new(pre, post, gr);
+            // only send the bands that are actually being used
+            let num_bands = params.num_bands.value() as usize;
+            let band_gr = if num_bands > 1 {
+                band_reduced[..num_bands]
+                    .iter()
+                    .map(|gr| gr.load(Ordering::Relaxed))
+                    .collect()
+            } else {
+                Vec::new()
+            };
+            let message = Amplitude::new(pre, post, gr, band_gr);
             ctx.send_json(json!(message)).expect("OH NO!");
 
             // once we've sent our pending updates to the GUI, we can clear our event buffer;
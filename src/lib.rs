@@ -1,62 +1,66 @@
-mod dsp;
+pub mod dsp;
 pub mod editor;
 
-mod params;
+pub mod params;
+pub mod preset;
+pub mod processor;
 
-use dsp::{Compressor, RmsLevelDetector};
+use dsp::MAX_BANDS;
 use editor::create_editor;
 use nih_plug::prelude::*;
 use params::CompressorParams;
+use processor::Processor;
 
-use std::{
-    collections::VecDeque,
-    sync::{atomic::Ordering, Arc},
-};
+use std::sync::{atomic::Ordering, Arc};
 
 pub const MAX_BUFFER_SIZE: f32 = 0.03;
+/// The longest lookahead (in seconds) that the delay lines can hold.
+pub const MAX_LOOKAHEAD: f32 = 0.1;
+/// How long (in seconds) the gain reduction is averaged over for `MakeupMode::Average`.
+pub const AUTO_MAKEUP_TIME: f32 = 3.0;
+/// How far ahead (in seconds) the limiter looks. This is added to the latency when the limiter is on.
+pub const LIMITER_LOOKAHEAD: f32 = 0.0015;
 
 pub struct CompressorPlugin {
-    sample_rate: f32,
     params: Arc<CompressorParams>,
-    compressors: [Compressor; 2],
-    shared_rms: RmsLevelDetector,
+    /// Everything that actually touches the audio, see `Processor`.
+    processor: Processor,
+    /// The latency (in samples) that was last reported to the host.
+    latency: u32,
     pre_amplitude: Arc<AtomicF32>,
     post_amplitude: Arc<AtomicF32>,
     amt_reduced: Arc<AtomicF32>,
+    /// The gain reduction of each band in multiband mode.
+    band_reduced: Arc<[AtomicF32; MAX_BANDS]>,
 }
 
 impl Default for CompressorPlugin {
     fn default() -> Self {
         Self {
-            // this doesn't really matter, as long as we set everything correctly in initialize()
-            sample_rate: 0.0,
             params: Arc::new(CompressorParams::default()),
-            // TODO: FIX THIS LMAO
-            compressors: [Compressor::new(), Compressor::new()],
-            shared_rms: RmsLevelDetector::default(),
+            // this doesn't really matter, as long as we set everything correctly in initialize()
+            processor: Processor::default(),
+            latency: 0,
             pre_amplitude: Arc::new(AtomicF32::new(0.0)),
             post_amplitude: Arc::new(AtomicF32::new(0.0)),
             amt_reduced: Arc::new(AtomicF32::new(0.0)),
+            band_reduced: Arc::new(Default::default()),
         }
     }
 }
 
 impl CompressorPlugin {
-    fn initialize_rms_buffers(&mut self) {
-        let max_buffer_length = (self.sample_rate * MAX_BUFFER_SIZE) as usize;
-
-        self.shared_rms.buffer = VecDeque::with_capacity(max_buffer_length);
-
-        for compressor in &mut self.compressors {
-            compressor.rms.buffer = VecDeque::with_capacity(max_buffer_length);
-        }
+    /// The latency introduced by lookahead, oversampling (and the limiter, if it's on), in samples.
+    fn latency_samples(&self) -> u32 {
+        self.processor.latency(
+            self.params.lookahead.value(),
+            self.params.limiter_enabled.value(),
+        )
     }
-    fn resize_rms_buffers(&mut self, new_size: usize) {
-        // resize independent and shared RMS
-        self.shared_rms.buffer.resize_with(new_size, || 0.0);
-        for compressor in &mut self.compressors {
-            compressor.rms.buffer.resize_with(new_size, || 0.0);
-        }
+    /// Sets the RMS windows to the length of the `rms_buffer_size` parameter.
+    fn update_rms_length(&mut self) {
+        let buffer_size = self.params.rms_buffer_size.smoothed.next();
+        self.processor.set_rms_buffer_size(buffer_size);
     }
 }
 
@@ -96,16 +100,18 @@ impl Plugin for CompressorPlugin {
         &mut self,
         _audio_io_layout: &AudioIOLayout,
         buffer_config: &BufferConfig,
-        _context: &mut impl InitContext<Self>,
+        context: &mut impl InitContext<Self>,
     ) -> bool {
         // NOTE:
         // i don't really have a good way of knowing if this code will actually work correctly
 
-        let sample_rate = buffer_config.sample_rate;
-        self.sample_rate = sample_rate;
-        let actual_size = (sample_rate * self.params.rms_buffer_size.value()) as usize;
-        self.initialize_rms_buffers();
-        self.resize_rms_buffers(actual_size);
+        self.processor = Processor::new(buffer_config.sample_rate);
+        self.processor
+            .set_oversampling(self.params.oversampling.value().factor());
+        self.update_rms_length();
+
+        self.latency = self.latency_samples();
+        context.set_latency_samples(self.latency);
 
         true
     }
@@ -117,64 +123,67 @@ impl Plugin for CompressorPlugin {
         context: &mut impl ProcessContext<Self>,
     ) -> ProcessStatus {
         let num_channels = buffer.channels();
-        let aux_samples = aux.inputs[0].iter_samples();
-        // TODO:
-        // use smoothed vals
-        let input_gain = self.params.input_gain.value();
-        let dry_wet = self.params.dry_wet.value();
-        let output_gain = self.params.output_gain.value();
-
-        for (mut channel_samples, mut aux_channel_samples) in buffer.iter_samples().zip(aux_samples)
-        {
-            let mut pre_amplitude = 0.0;
-            let mut post_amplitude = 0.0;
-            let mut amt_reduced = 0.0;
-
-            let num_samples = channel_samples.len();
-
-            // this loops twice, once for L/R channels
-            for i in 0..num_channels {
-                let sample = channel_samples.get_mut(i).unwrap();
-                let _aux_sample = aux_channel_samples.get_mut(i).unwrap();
-
-                let (mut pre_processed, processed, amount_reduced) = self.compressors[i].process(
-                    *sample,
-                    &self.params,
-                    &mut self.shared_rms,
-                    self.sample_rate,
-                );
-                amt_reduced += amount_reduced;
-                // TODO:
-                // might have to play around with the order of operations here
-                pre_processed *= input_gain;
-                pre_amplitude += pre_processed;
-
-                // blend based on dry_wet
-                let mut blended_output = (1.0 - dry_wet) * pre_processed + dry_wet * processed;
-
-                // finally, modify with output gain
-                blended_output *= output_gain;
-                post_amplitude += blended_output;
-
+        // the mono layout doesn't have an aux input, in which case we can only use the main input as the key
+        let mut aux_samples = aux
+            .inputs
+            .get_mut(0)
+            .map(|aux_buffer| aux_buffer.iter_samples());
+        // switching the oversampling factor doesn't allocate, so it can happen at any time
+        self.processor
+            .set_oversampling(self.params.oversampling.value().factor());
+
+        for mut channel_samples in buffer.iter_samples() {
+            let aux_channel_samples = aux_samples.as_mut().and_then(|samples| samples.next());
+            let settings = self.params.processor_settings(num_channels);
+
+            let mut frame = [0.0; 2];
+            for (i, sample) in frame.iter_mut().enumerate().take(num_channels) {
+                *sample = *channel_samples.get_mut(i).unwrap();
+            }
+            // a channel that's missing from the aux input falls back to the main input
+            let aux_frame = aux_channel_samples.map(|mut aux_channel_samples| {
+                let num_aux_channels = aux_channel_samples.len().min(num_channels);
+                let mut aux_frame = [0.0; 2];
+                for (i, aux_sample) in aux_frame.iter_mut().enumerate().take(num_aux_channels) {
+                    *aux_sample = *aux_channel_samples.get_mut(i).unwrap();
+                }
+                (aux_frame, num_aux_channels)
+            });
+
+            let meters = self.processor.process(
+                &mut frame[..num_channels],
+                aux_frame
+                    .as_ref()
+                    .map(|(aux_frame, num_aux_channels)| &aux_frame[..*num_aux_channels]),
+                &settings,
+            );
+
+            for (i, output) in frame.iter().enumerate().take(num_channels) {
                 // and we're done!
-                *sample = blended_output;
+                *channel_samples.get_mut(i).unwrap() = *output;
             }
 
-            pre_amplitude = (pre_amplitude / num_samples as f32).abs();
-            post_amplitude = (post_amplitude / num_samples as f32).abs();
-            amt_reduced = (amt_reduced / num_samples as f32).abs();
-
-            self.pre_amplitude.store(pre_amplitude, Ordering::Relaxed);
-            self.post_amplitude.store(post_amplitude, Ordering::Relaxed);
+            let amt_reduced =
+                meters.amount_reduced[..num_channels].iter().sum::<f32>() / num_channels as f32;
+            self.pre_amplitude
+                .store(meters.pre_amplitude, Ordering::Relaxed);
+            self.post_amplitude
+                .store(meters.post_amplitude, Ordering::Relaxed);
             self.amt_reduced.store(amt_reduced, Ordering::Relaxed);
+            for (meter, reduced) in self.band_reduced.iter().zip(meters.band_reduced) {
+                meter.store(reduced, Ordering::Relaxed);
+            }
         }
 
         if self.params.rms_update.swap(false, Ordering::Relaxed) {
-            let new_buffer_size = self.params.rms_buffer_size.smoothed.next();
-            let new_size = (self.sample_rate * new_buffer_size) as usize;
+            self.update_rms_length();
+        }
 
-            context.set_latency_samples(new_size as u32);
-            self.resize_rms_buffers(new_size);
+        // the lookahead, the oversampling filters and the limiter all delay the output
+        let latency = self.latency_samples();
+        if latency != self.latency {
+            self.latency = latency;
+            context.set_latency_samples(latency);
         }
 
         ProcessStatus::Normal
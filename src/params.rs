@@ -1,8 +1,19 @@
-use crate::{params::Parameter::*, MAX_BUFFER_SIZE};
+use crate::{
+    dsp::{
+        transfer_curve, BlendMode, Character, CompressorSettings, DeEsserMode, DeEsserSettings,
+        DynamicsCurve, EnvelopeType, LevelDetectionType, ParallelSettings, SaturationPosition,
+        SaturationSettings, SaturationType, SidechainFilterSettings, SidechainFilterType, Topology,
+        TransientShaperSettings, MAX_BANDS,
+    },
+    params::Parameter::*,
+    preset::{BandPreset, MultibandPreset, Preset},
+    processor::ProcessorSettings,
+    MAX_BUFFER_SIZE, MAX_LOOKAHEAD,
+};
 use nih_plug::{
     formatters::{self, v2s_f32_rounded},
-    params::{FloatParam, Params},
-    prelude::{FloatRange, SmoothingStyle},
+    params::{BoolParam, EnumParam, FloatParam, IntParam, Params},
+    prelude::{Enum, FloatRange, IntRange, SmoothingStyle},
     util,
 };
 use serde::{Deserialize, Serialize};
@@ -21,21 +32,44 @@ pub const DEFAULT_KNEE: f32 = 5.0;
 pub const DEFAULT_ATTACK_TIME: f32 = 0.001;
 pub const DEFAULT_RELEASE_TIME: f32 = 0.05;
 pub const DEFAULT_BUFFER_SIZE: f32 = 0.01;
+pub const DEFAULT_EXPANSION_RANGE: f32 = 40.0;
+pub const DEFAULT_UPWARD_RANGE: f32 = 12.0;
+/// The top of the range control **in decibels**, which means there's no limit at all.
+pub const MAX_RANGE: f32 = 60.0;
+/// Unlimited, so that sessions from before the range control existed still sound the same.
+pub const DEFAULT_RANGE: f32 = MAX_RANGE;
+/// The default center frequency of the de-esser's band **in Hz**.
+pub const DEFAULT_DE_ESSER_FREQUENCY: f32 = 6500.0;
+/// The default crossover frequencies **in Hz**, from low to high.
+pub const DEFAULT_CROSSOVERS: [f32; MAX_BANDS - 1] = [120.0, 1000.0, 6000.0];
 
 // TODO:
 // parameterize buffer size for RMS
 
-// "Run Test" (at least, in vscode) will (re-) generate the TS bindings
-#[derive(Deserialize, Serialize, TS, Debug, Clone, PartialEq)]
-#[ts(export_to = "../gui/bindings/Parameter.ts")]
-#[ts(export)]
-// TODO:
-// document this
-pub enum Parameter {
+/// Declares the `Parameter` enum, along with `NUM_PARAMETERS` (the number of variants) so that the two can't get out of sync.
+macro_rules! parameters {
+    ($($variant:ident($value:ty)),* $(,)?) => {
+        // "Run Test" (at least, in vscode) will (re-) generate the TS bindings
+        #[derive(Deserialize, Serialize, TS, Debug, Clone, PartialEq)]
+        #[ts(export_to = "../gui/bindings/Parameter.ts")]
+        #[ts(export)]
+        // TODO:
+        // document this
+        pub enum Parameter {
+            $($variant($value)),*
+        }
+
+        /// The number of `Parameter` variants. The event buffer never holds more than one event of each,
+        /// so this is all the room it needs to never allocate on the audio thread.
+        const NUM_PARAMETERS: usize = [$(stringify!($variant)),*].len();
+    };
+}
+parameters! {
     Ratio(f32),
     Threshold(f32),
     AttackTime(f32),
     ReleaseTime(f32),
+    HoldTime(f32),
     KneeWidth(f32),
     InputGain(f32),
     OutputGain(f32),
@@ -43,6 +77,12 @@ pub enum Parameter {
     RmsBufferSize(f32),
     Lookahead(f32),
     RmsMix(f32),
+    Range(f32),
+    DeEsserFrequency(f32),
+    DeEsserListen(bool),
+    TransientShaper(bool),
+    AttackEmphasis(f32),
+    SustainEmphasis(f32),
 }
 
 #[derive(Deserialize, Serialize, TS)]
@@ -53,6 +93,7 @@ pub enum Message {
     WindowClosed,
     ParameterUpdate(Parameter),
     Amplitude(Amplitude),
+    TransferCurve(TransferCurve),
 }
 // TODO:
 // make sure generated TS types arent snake_case
@@ -63,21 +104,151 @@ pub struct Amplitude {
     pre_amplitude: f32,
     post_amplitude: f32,
     amt_reduced: f32,
+    /// The gain reduction of each band, from low to high. This is empty unless multiband is on.
+    band_reduced: Vec<f32>,
 }
 impl Amplitude {
-    pub fn new(pre: f32, post: f32, reduced: f32) -> Self {
+    pub fn new(pre: f32, post: f32, reduced: f32, band_reduced: Vec<f32>) -> Self {
         Amplitude {
             pre_amplitude: pre,
             post_amplitude: post,
             amt_reduced: reduced,
+            band_reduced,
         }
     }
 }
 
-// TODO:
-// dont use a const like this lol
+/// The static curve of the compressor, sampled so the GUI can draw it.
+/// Both fields are **in decibels**, and `output_db[i]` is the output level for `input_db[i]`.
+#[derive(Deserialize, Serialize, TS, Debug, Clone, PartialEq)]
+#[ts(export_to = "../gui/bindings/TransferCurve.ts")]
+#[ts(export)]
+pub struct TransferCurve {
+    input_db: Vec<f32>,
+    output_db: Vec<f32>,
+}
+impl TransferCurve {
+    /// The lowest input level on the curve, which is also the bottom of the GUI's graph.
+    pub const MIN_DB: f32 = -100.0;
+    pub const MAX_DB: f32 = 0.0;
+    /// One point per dB is plenty, even with a narrow knee.
+    pub const NUM_POINTS: usize = 101;
+
+    /// Samples the curve the same way the gain computer uses it, including whatever the `character` does to it.
+    pub fn new(curve: DynamicsCurve, character: Character) -> Self {
+        let (input_db, output_db) = transfer_curve(
+            curve,
+            character,
+            Self::MIN_DB,
+            Self::MAX_DB,
+            Self::NUM_POINTS,
+        )
+        .unzip();
+        TransferCurve {
+            input_db,
+            output_db,
+        }
+    }
+}
+
+/// Variants represent which signal the compressor listens to when updating its internal gain.
+/// The gain reduction is always applied to the main input, no matter which source is used.
+#[derive(Enum, Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SidechainSource {
+    /// Use the main input, like a regular compressor.
+    #[id = "internal"]
+    Internal,
+    /// Use the auxiliary (sidechain) input, for ducking.
+    #[id = "external"]
+    External,
+    /// Use an equal blend of the main and auxiliary inputs.
+    #[id = "blend"]
+    Blend,
+}
+
+/// Variants represent which two signals the two compressors work on.
+#[derive(Enum, Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum StereoMode {
+    /// Compress the left and right channels, like a regular compressor.
+    #[id = "lr"]
+    #[name = "L/R"]
+    LeftRight,
+    /// Compress the mid (what L and R have in common) and side (the difference between them) channels.
+    #[id = "ms"]
+    #[name = "M/S"]
+    MidSide,
+    /// Only compress the mid channel. The side channel is left alone.
+    #[id = "mid"]
+    #[name = "Mid Only"]
+    MidOnly,
+    /// Only compress the side channel. The mid channel is left alone.
+    #[id = "side"]
+    #[name = "Side Only"]
+    SideOnly,
+}
+impl StereoMode {
+    /// Whether the channels are encoded to mid/side before compressing.
+    pub fn is_mid_side(&self) -> bool {
+        *self != StereoMode::LeftRight
+    }
+    /// Whether the given channel (0 is L or M, 1 is R or S) should be compressed at all.
+    pub fn compresses(&self, channel: usize) -> bool {
+        match self {
+            StereoMode::MidOnly => channel == 0,
+            StereoMode::SideOnly => channel == 1,
+            _ => true,
+        }
+    }
+}
 
-const NUM_PARAMETERS: usize = 11;
+/// Variants represent how the makeup gain is worked out automatically.
+#[derive(Enum, Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum MakeupMode {
+    /// No automatic makeup gain, only `output_gain`.
+    #[id = "off"]
+    Off,
+    /// Make up for what the static curve does to a full-scale signal. This reacts instantly to the threshold, ratio, etc.
+    /// A curve that boosts a full-scale signal (with upward expansion) gets turned down instead. In multiband mode, bypassed bands don't count.
+    #[id = "static"]
+    Static,
+    /// Make up for the average gain reduction over the last few seconds. This follows the actual material.
+    #[id = "average"]
+    Average,
+}
+
+/// Variants represent how many times faster than the host's sample rate the compressors run.
+#[derive(Enum, Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub enum Oversampling {
+    #[id = "1x"]
+    #[serde(rename = "1x")]
+    #[name = "Off"]
+    X1,
+    #[id = "2x"]
+    #[serde(rename = "2x")]
+    #[name = "2x"]
+    X2,
+    #[id = "4x"]
+    #[serde(rename = "4x")]
+    #[name = "4x"]
+    X4,
+    #[id = "8x"]
+    #[serde(rename = "8x")]
+    #[name = "8x"]
+    X8,
+}
+impl Oversampling {
+    pub fn factor(&self) -> usize {
+        match self {
+            Oversampling::X1 => 1,
+            Oversampling::X2 => 2,
+            Oversampling::X4 => 4,
+            Oversampling::X8 => 8,
+        }
+    }
+}
 
 /// Parameters for compressor.
 /// **NOTE**: In this documentation I've used the term "level" instead of "signal."
@@ -110,47 +281,422 @@ pub struct CompressorParams {
     /// **NOTE**: The actual underlying value is the release filter coefficient for the compressor, however the value is converted and displayed in (milli)seconds.
     #[id = "release"]
     pub release_time: FloatParam,
+    /// How long the compressor keeps the gain reduction where it is after *the level* starts falling, before the release kicks in.
+    /// This stops fast releases from following the individual cycles of low-frequency material, which sounds like distortion.
+    #[id = "hold"]
+    pub hold_time: FloatParam,
+    /// How much the release time follows the material, from `0.0` (always `release_time`) to `1.0`.
+    /// Turning this up makes the release shorter after transients and longer under sustained compression, which cuts down on pumping.
+    #[id = "autorelease"]
+    pub auto_release: FloatParam,
     /// The knee width **in decibels**. This smooths the transition between compression and no compression around the threshold.
     /// If you'd like a *hard-knee compressor*, set this value to `0.0`.
     #[id = "knee"]
     pub knee_width: FloatParam,
+    /// The ratio for downward expansion, which turns *the level* down even more when it's below the threshold.
+    /// For example, a ratio of `2.0` means that for every 1db that *the level* is below the `threshold`, it's turned down by another 1db.
+    /// A very high ratio makes this a gate. `1.0` turns it off.
+    #[id = "expratio"]
+    pub expansion_ratio: FloatParam,
+    /// The most that downward expansion can turn *the level* down, **in decibels**.
+    #[id = "exprange"]
+    pub expansion_range: FloatParam,
+    /// The ratio for upward compression, which brings *the level* up towards the threshold when it's below it.
+    /// This works just like `ratio`, but below the threshold and in the other direction. `1.0` turns it off.
+    #[id = "upratio"]
+    pub upward_compression_ratio: FloatParam,
+    /// The most that upward compression can turn *the level* up, **in decibels**.
+    #[id = "uprange"]
+    pub upward_compression_range: FloatParam,
+    /// The ratio for upward expansion, which turns *the level* up even more when it's above the threshold. `1.0` turns it off.
+    #[id = "upexpratio"]
+    pub upward_expansion_ratio: FloatParam,
+    /// The most that upward expansion can turn *the level* up, **in decibels**.
+    #[id = "upexprange"]
+    pub upward_expansion_range: FloatParam,
+    /// The most gain reduction **in decibels** that the compressor will ever apply, however high the ratio is.
+    /// A high ratio with a small range gives gentle, "ceiling-limited" compression.
+    /// At the top of the range (`MAX_RANGE`, the default) there's no limit at all.
+    #[id = "range"]
+    pub range: FloatParam,
     /// Modify the gain of the incoming signal ***before*** compression.
     #[id = "ingain"]
     pub input_gain: FloatParam,
     /// Modify the gain of the incoming signal ***after*** compression ***AND*** after dry/wet has been applied.
     #[id = "outgain"]
     pub output_gain: FloatParam,
+    /// Automatically turns the compressed signal back up to make up for the gain reduction,
+    /// so it's about as loud as the dry signal no matter where the threshold and ratio are.
+    /// This happens before `dry_wet` and `output_gain`.
+    #[id = "automakeup"]
+    pub makeup_mode: EnumParam<MakeupMode>,
     /// Blends the pre-compressed signal with the processed, compressed signal.
     /// `1.0` (100%) means that only the compressed signal will be output,
     /// while `0.0` (0%) means that essentially, no compression is applied.  
     #[id = "drywet"]
     pub dry_wet: FloatParam,
+    /// How `dry_wet` blends the two signals. `EqualPower` keeps the loudness steady, which suits parallel compression.
+    #[id = "blend"]
+    pub blend_mode: EnumParam<BlendMode>,
+    /// Tilts the compressed signal around `WET_TILT_FREQUENCY`, **in decibels**. The dry signal is never EQ'd.
+    #[id = "wettilt"]
+    pub wet_tilt: FloatParam,
+    /// Turns the compressed signal up or down before the blend, **in decibels**. This is on top of the makeup gain.
+    #[id = "wetgain"]
+    pub wet_gain: FloatParam,
+
+    /// Where the saturation stage goes: before the gain element, after it, or nowhere.
+    /// The saturation runs at the oversampled rate, so turning up `oversampling` cuts down on its aliasing.
+    #[id = "satpos"]
+    pub saturation_position: EnumParam<SaturationPosition>,
+    /// The shape of the saturation curve.
+    #[id = "sattype"]
+    pub saturation_type: EnumParam<SaturationType>,
+    /// How hard the signal is pushed into the saturation curve, **in decibels**.
+    /// The level is turned back down after the curve, so this changes the amount of coloration rather than the loudness.
+    #[id = "satdrive"]
+    pub saturation_drive: FloatParam,
+    /// Blends the clean and saturated signals. This is separate from `dry_wet`.
+    #[id = "satmix"]
+    pub saturation_mix: FloatParam,
+
+    /// Turns on a brickwall limiter at the very end, after `output_gain`.
+    /// This adds a little bit of latency (see `LIMITER_LOOKAHEAD`).
+    #[id = "limiter"]
+    pub limiter_enabled: BoolParam,
+    /// The level **in decibels** that the limiter never lets the output go over, including peaks in between samples.
+    #[id = "ceiling"]
+    pub limiter_ceiling: FloatParam,
+    /// How long it takes the limiter to recover after a peak, **in seconds**.
+    #[id = "limrelease"]
+    pub limiter_release: FloatParam,
 
     /// The length of time (in seconds) of input that the RMS will use to calculate its gain.
     /// For example, 30 milliseconds means that the RMS will capture the last 30 milliseconds of input as its gain.
     #[id = "bufsize"]
     pub rms_buffer_size: FloatParam,
 
+    /// Runs the level detection and the gain stage at a higher sample rate, so that fast attack and release times don't alias.
+    /// The half-band filters add some latency, which is reported to the host.
+    #[id = "oversampling"]
+    pub oversampling: EnumParam<Oversampling>,
+
     /// The amount of time in seconds that the output is delayed.
     /// I know there's a better definition, I just can't think of it lol
+    ///
+    /// This is independent of `rms_buffer_size`, and is the only thing reported to the host as latency.
     #[id = "lookahead"]
     pub lookahead: FloatParam,
 
-    /// Blends the gain of the independent (L/R) and shared RMS.
-    /// A value of 0.0 means the L/R compressors calculate their compression *only* based on their own RMS state,
-    /// while a value of 1.0 means the L/R compressors will use the gain state of the RMS shared between them ONLY.
+    /// Blends the independent (L/R) and shared levels.
+    /// A value of 0.0 means the L/R compressors calculate their compression *only* based on their own detector state,
+    /// while a value of 1.0 means the L/R compressors will use the level shared between them ONLY (the louder of the two, see `link_levels()`).
+    ///
+    /// This works the same way for every `LevelDetectionType`, not just RMS.
     #[id = "rmsmix"]
     pub rms_mix: FloatParam,
+
+    /// The signal that drives the level detection.
+    /// When set to `External`, the aux input is used as the key signal, and the main input is only used for output.
+    #[id = "scsource"]
+    pub sidechain_source: EnumParam<SidechainSource>,
+    /// The filter that the key signal (internal or external) goes through before level detection.
+    /// Only the detectors hear this, the audio itself is never filtered.
+    #[id = "scfilter"]
+    pub sidechain_filter_type: EnumParam<SidechainFilterType>,
+    /// The cutoff (or center) frequency of the sidechain filter, **in Hz**.
+    #[id = "scfreq"]
+    pub sidechain_filter_frequency: FloatParam,
+    /// The Q of the sidechain filter. Higher values make the band-pass narrower.
+    #[id = "scq"]
+    pub sidechain_filter_q: FloatParam,
+    /// Sends the filtered key signal to the output instead of the compressed audio, so you can hear what the detectors hear.
+    #[id = "sclisten"]
+    pub sidechain_listen: BoolParam,
+
+    /// Turns the compressor into a de-esser. The key goes through a band-pass around `de_esser_frequency`
+    /// (instead of the sidechain filter), and the gain reduction is applied to either the whole signal or just that band.
+    /// The de-esser always works on the full band, even when `num_bands` is more than 1.
+    #[id = "deesser"]
+    pub de_esser_mode: EnumParam<DeEsserMode>,
+    /// The center of the sibilant band, **in Hz**.
+    #[id = "deessfreq"]
+    pub de_esser_frequency: FloatParam,
+    /// Sends the band-passed key to the output, so you can tune `de_esser_frequency` by ear.
+    #[id = "deesslisten"]
+    pub de_esser_listen: BoolParam,
+
+    /// The method used to measure *the level*.
+    /// Peak reacts to every transient, RMS follows the average loudness,
+    /// and true-peak also catches the peaks that happen in between samples.
+    #[id = "detector"]
+    pub level_detection_type: EnumParam<LevelDetectionType>,
+
+    /// Whether the level detection listens to the input (feed-forward) or to the input with the compressor's own gain reduction applied (feedback).
+    /// This applies to both the independent and shared levels.
+    #[id = "topology"]
+    pub topology: EnumParam<Topology>,
+
+    /// How the attack and release are applied.
+    /// `Level` smooths the detected level before the gain computer,
+    /// while the other options smooth the gain reduction (in dB) after it.
+    #[id = "envelope"]
+    pub envelope_type: EnumParam<EnvelopeType>,
+
+    /// Which hardware the compressor behaves like.
+    /// Anything other than `Vca` overrides the detector and topology, and changes the knee and release.
+    #[id = "character"]
+    pub character: EnumParam<Character>,
+
+    /// Turns the compressor into a transient designer, which follows the shape of the key instead of its level.
+    /// The threshold and ratio don't do anything in this mode, while `attack_time` and `release_time` set the slow envelope.
+    #[id = "transient"]
+    pub transient_shaper: BoolParam,
+    /// How much the start of each hit is turned up (or down, when negative), **in decibels**.
+    #[id = "attackemph"]
+    pub attack_emphasis: FloatParam,
+    /// How much the tail of each hit is turned up (or down, when negative), **in decibels**.
+    #[id = "sustainemph"]
+    pub sustain_emphasis: FloatParam,
+
+    /// Whether to compress the left and right channels, or the mid and side channels (or only one of them).
+    /// This only does anything with the stereo layout.
+    #[id = "stereo"]
+    pub stereo_mode: EnumParam<StereoMode>,
+    /// Added to the threshold of the mid channel, **in decibels**. This is ignored in L/R mode.
+    #[id = "midthresh"]
+    pub mid_threshold_offset: FloatParam,
+    /// Added to the ratio of the mid channel. This is ignored in L/R mode.
+    #[id = "midratio"]
+    pub mid_ratio_offset: FloatParam,
+    /// Added to the threshold of the side channel, **in decibels**. This is ignored in L/R mode.
+    #[id = "sidethresh"]
+    pub side_threshold_offset: FloatParam,
+    /// Added to the ratio of the side channel. This is ignored in L/R mode.
+    #[id = "sideratio"]
+    pub side_ratio_offset: FloatParam,
+
+    /// How many bands the signal is split into. With 1 band, this is a regular (full-band) compressor using the main controls,
+    /// otherwise each band is compressed separately using its own controls in `bands`.
+    #[id = "bands"]
+    pub num_bands: IntParam,
+    /// The frequency **in Hz** between the 1st and 2nd band.
+    #[id = "xover1"]
+    pub crossover_1: FloatParam,
+    /// The frequency **in Hz** between the 2nd and 3rd band.
+    #[id = "xover2"]
+    pub crossover_2: FloatParam,
+    /// The frequency **in Hz** between the 3rd and 4th band.
+    #[id = "xover3"]
+    pub crossover_3: FloatParam,
+    /// The controls for each band in multiband mode, from low to high.
+    #[nested(array, group = "Band")]
+    pub bands: [BandParams; MAX_BANDS],
+}
+
+/// Parameters for a single band in multiband mode.
+/// These work exactly like the controls with the same name in `CompressorParams`, but only for this band.
+#[derive(Params)]
+pub struct BandParams {
+    /// **In decibels.**
+    #[id = "threshold"]
+    pub threshold: FloatParam,
+    #[id = "ratio"]
+    pub ratio: FloatParam,
+    /// **In seconds.**
+    #[id = "attack"]
+    pub attack_time: FloatParam,
+    /// **In seconds.**
+    #[id = "release"]
+    pub release_time: FloatParam,
+    /// **In decibels.**
+    #[id = "knee"]
+    pub knee_width: FloatParam,
+    /// When any band is soloed, only the soloed bands are heard.
+    #[id = "solo"]
+    pub solo: BoolParam,
+    /// Passes this band through without compressing it.
+    #[id = "bypass"]
+    pub bypass: BoolParam,
+}
+
+impl Default for BandParams {
+    fn default() -> Self {
+        // the same controls as the main ones, just without the GUI callbacks
+        Self {
+            threshold: threshold_param(),
+            ratio: ratio_param(),
+            attack_time: attack_time_param(),
+            release_time: release_time_param(),
+            knee_width: knee_width_param(),
+            solo: BoolParam::new("Solo", false),
+            bypass: BoolParam::new("Bypass", false),
+        }
+    }
 }
 
 impl CompressorParams {
+    /// Reads the current values of everything the `Compressor` needs.
+    /// This advances the parameter smoothers, so it should be called exactly once per sample.
+    pub fn compressor_settings(&self) -> CompressorSettings {
+        CompressorSettings {
+            curve: self.dynamics_curve(),
+            attack_time: self.attack_time.smoothed.next(),
+            release_time: self.release_time.smoothed.next(),
+            hold_time: self.hold_time.smoothed.next(),
+            lookahead: self.lookahead.smoothed.next(),
+            rms_mix: self.rms_mix.smoothed.next(),
+            auto_release: self.auto_release.smoothed.next(),
+            level_detection_type: self.level_detection_type.value(),
+            topology: self.topology.value(),
+            envelope_type: self.envelope_type.value(),
+            character: self.character.value(),
+            transient_shaper: TransientShaperSettings {
+                enabled: self.transient_shaper.value(),
+                attack_emphasis: self.attack_emphasis.smoothed.next(),
+                sustain_emphasis: self.sustain_emphasis.smoothed.next(),
+            },
+        }
+    }
+
+    /// Reads the current shape of the static curve.
+    /// Unlike `compressor_settings()`, this doesn't touch the smoothers, so it's safe to call from anywhere (like the editor).
+    pub fn dynamics_curve(&self) -> DynamicsCurve {
+        DynamicsCurve {
+            threshold: self.threshold.value(),
+            ratio: self.ratio.value(),
+            knee_width: self.knee_width.value(),
+            expansion_ratio: self.expansion_ratio.value(),
+            expansion_range: self.expansion_range.value(),
+            upward_compression_ratio: self.upward_compression_ratio.value(),
+            upward_compression_range: self.upward_compression_range.value(),
+            upward_expansion_ratio: self.upward_expansion_ratio.value(),
+            upward_expansion_range: self.upward_expansion_range.value(),
+            range: self.range.value(),
+        }
+    }
+
+    /// Reads the current values of the multiband controls.
+    /// Like `compressor_settings()`, this advances the parameter smoothers, so it should be called exactly once per sample.
+    pub fn multiband_preset(&self) -> MultibandPreset {
+        MultibandPreset {
+            num_bands: self.num_bands.value() as usize,
+            crossovers: [
+                self.crossover_1.smoothed.next(),
+                self.crossover_2.smoothed.next(),
+                self.crossover_3.smoothed.next(),
+            ],
+            bands: std::array::from_fn(|i| {
+                let band = &self.bands[i];
+                BandPreset {
+                    threshold: band.threshold.value(),
+                    ratio: band.ratio.value(),
+                    knee_width: band.knee_width.value(),
+                    attack_time: band.attack_time.smoothed.next(),
+                    release_time: band.release_time.smoothed.next(),
+                    solo: band.solo.value(),
+                    bypass: band.bypass.value(),
+                }
+            }),
+        }
+    }
+
+    /// Reads the current values of the sidechain filter controls. This should only be called once per sample.
+    pub fn sidechain_filter_settings(&self) -> SidechainFilterSettings {
+        SidechainFilterSettings {
+            filter_type: self.sidechain_filter_type.value(),
+            frequency: self.sidechain_filter_frequency.smoothed.next(),
+            q: self.sidechain_filter_q.smoothed.next(),
+        }
+    }
+
+    /// Reads the current values of the saturation controls. This should only be called once per sample.
+    pub fn saturation_settings(&self) -> SaturationSettings {
+        SaturationSettings {
+            position: self.saturation_position.value(),
+            saturation_type: self.saturation_type.value(),
+            drive: self.saturation_drive.smoothed.next(),
+            mix: self.saturation_mix.smoothed.next(),
+        }
+    }
+
+    /// Reads the current values of the parallel compression controls. This should only be called once per sample.
+    pub fn parallel_settings(&self) -> ParallelSettings {
+        ParallelSettings {
+            blend_mode: self.blend_mode.value(),
+            mix: self.dry_wet.smoothed.next(),
+            wet_tilt: self.wet_tilt.smoothed.next(),
+            wet_gain: self.wet_gain.smoothed.next(),
+        }
+    }
+
+    /// Reads the current values of the de-esser controls. This should only be called once per sample.
+    pub fn de_esser_settings(&self) -> DeEsserSettings {
+        DeEsserSettings {
+            mode: self.de_esser_mode.value(),
+            frequency: self.de_esser_frequency.smoothed.next(),
+        }
+    }
+
+    /// Reads the current values of every parameter into a `Preset`.
+    /// Like `compressor_settings()`, this advances the parameter smoothers, so it should be called exactly once per sample.
+    pub fn preset(&self) -> Preset {
+        Preset {
+            settings: self.compressor_settings(),
+            rms_buffer_size: self.rms_buffer_size.value(),
+            oversampling: self.oversampling.value(),
+            multiband: self.multiband_preset(),
+            sidechain_source: self.sidechain_source.value(),
+            sidechain_filter: self.sidechain_filter_settings(),
+            sidechain_listen: self.sidechain_listen.value(),
+            de_esser: self.de_esser_settings(),
+            de_esser_listen: self.de_esser_listen.value(),
+            saturation: self.saturation_settings(),
+            parallel: self.parallel_settings(),
+            stereo_mode: self.stereo_mode.value(),
+            mid_side_offsets: self.mid_side_offsets(),
+            makeup_mode: self.makeup_mode.value(),
+            // TODO:
+            // use smoothed vals
+            input_gain: util::gain_to_db(self.input_gain.value()),
+            output_gain: util::gain_to_db(self.output_gain.value()),
+            limiter_enabled: self.limiter_enabled.value(),
+            limiter_ceiling: self.limiter_ceiling.smoothed.next(),
+            limiter_release: self.limiter_release.value(),
+        }
+    }
+
+    /// Reads the current values of everything the `Processor` needs, for a frame of `num_channels` channels.
+    /// This goes through `preset()`, so the plugin and the offline tools turn their values into settings the exact same way.
+    /// Like `compressor_settings()`, this advances the parameter smoothers, so it should be called exactly once per sample.
+    pub fn processor_settings(&self, num_channels: usize) -> ProcessorSettings {
+        self.preset().processor_settings(num_channels)
+    }
+
+    /// The `(threshold, ratio)` offsets for the mid and side channels, in that order.
+    pub fn mid_side_offsets(&self) -> [(f32, f32); 2] {
+        [
+            (
+                self.mid_threshold_offset.value(),
+                self.mid_ratio_offset.value(),
+            ),
+            (
+                self.side_threshold_offset.value(),
+                self.side_ratio_offset.value(),
+            ),
+        ]
+    }
+
     /// Returns a tuple of the corresponding FloatParam and value based on a `ParameterEvent` input
-    pub fn get_param(&self, action: &Parameter) -> (&FloatParam, f32) {
-        match action {
+    ///
+    /// Returns `None` for the parameters that aren't a `FloatParam`, see `get_bool_param()` for those.
+    pub fn get_param(&self, action: &Parameter) -> Option<(&FloatParam, f32)> {
+        let param = match action {
             Ratio(value) => (&self.ratio, *value),
             Threshold(value) => (&self.threshold, *value),
             AttackTime(value) => (&self.attack_time, *value),
             ReleaseTime(value) => (&self.release_time, *value),
+            HoldTime(value) => (&self.hold_time, *value),
             KneeWidth(value) => (&self.knee_width, *value),
             InputGain(value) => (&self.input_gain, *value),
             OutputGain(value) => (&self.output_gain, *value),
@@ -158,21 +704,36 @@ impl CompressorParams {
             RmsBufferSize(value) => (&self.rms_buffer_size, *value),
             Lookahead(value) => (&self.lookahead, *value),
             RmsMix(value) => (&self.rms_mix, *value),
+            Range(value) => (&self.range, *value),
+            DeEsserFrequency(value) => (&self.de_esser_frequency, *value),
+            AttackEmphasis(value) => (&self.attack_emphasis, *value),
+            SustainEmphasis(value) => (&self.sustain_emphasis, *value),
+            DeEsserListen(_) | TransientShaper(_) => return None,
+        };
+        Some(param)
+    }
+
+    /// The `BoolParam` equivalent of `get_param()`.
+    pub fn get_bool_param(&self, action: &Parameter) -> Option<(&BoolParam, bool)> {
+        match action {
+            DeEsserListen(value) => Some((&self.de_esser_listen, *value)),
+            TransientShaper(value) => Some((&self.transient_shaper, *value)),
+            _ => None,
         }
     }
 }
 
 /// Creates a callback which pushes the given `ParameterEvent` to the `event_buffer`.
-/// The callback should be attached to a `FloatParam`
-fn generate_callback(
-    variant: fn(f32) -> Parameter,
+/// The callback should be attached to a parameter with the same plain type as the variant (usually a `FloatParam`).
+fn generate_callback<T: 'static>(
+    variant: fn(T) -> Parameter,
     event_buffer: &Arc<Mutex<Vec<Parameter>>>,
-) -> Arc<impl Fn(f32)> {
+) -> Arc<impl Fn(T)> {
     let event_buffer_clone = event_buffer.clone();
 
     // this is the callback that each parameter will fire when it updates
     // either from the GUI or from the DAW
-    Arc::new(move |value: f32| {
+    Arc::new(move |value: T| {
         // create an enum variant from the value
         let new_event = variant(value);
         let mut event_buffer_lock = event_buffer_clone
@@ -202,82 +763,75 @@ impl Default for CompressorParams {
         // I spent way too much time tuning these
         Self {
             // THRESHOLD
-            threshold: FloatParam::new(
-                "Threshold",
-                DEFAULT_THRESHOLD,
-                FloatRange::Skewed {
-                    min: -100.0,
-                    max: 5.0,
-                    factor: FloatRange::skew_factor(2.25),
-                },
-            )
-            // our threshold is already in dB land, so we don't need any conversion/formatting
-            // TODO: play with smoothing style/timing
-            .with_smoother(SmoothingStyle::Linear(10.0))
-            .with_unit(" dB")
-            // TODO:
-            // create a custom formatter for -inf dB
-            .with_value_to_string(formatters::v2s_f32_rounded(2))
-            .with_callback(generate_callback(Threshold, &event_buffer)),
+            threshold: threshold_param().with_callback(generate_callback(Threshold, &event_buffer)),
             // TODO:
             // do we need string_to_value..?
 
             // RATIO
-            ratio: FloatParam::new(
-                "Ratio",
-                DEFAULT_RATIO, // default compression ratio of 4:1 dB
-                FloatRange::Skewed {
-                    min: 1.0,
-                    max: 100.0,
-                    factor: FloatRange::skew_factor(-1.8),
-                },
-            )
-            .with_smoother(SmoothingStyle::Linear(10.0))
-            // TODO: customize formatter
-            .with_value_to_string(formatters::v2s_compression_ratio(2))
-            .with_unit(" dB")
-            .with_callback(generate_callback(Ratio, &event_buffer)),
+            ratio: ratio_param().with_callback(generate_callback(Ratio, &event_buffer)),
 
             // ATTACK TIME
-            attack_time: FloatParam::new(
-                "Attack Time",
-                DEFAULT_ATTACK_TIME,
-                FloatRange::Skewed {
-                    min: 0.0, // 0 seconds atk time, meaning the compressor takes effect instantly
-                    max: 1.0,
-                    factor: FloatRange::skew_factor(-2.0), // just happened to be right in the middle
-                },
-            )
-            .with_smoother(SmoothingStyle::Linear(10.0))
-            .with_value_to_string(v2s_time_formatter())
-            .with_callback(generate_callback(AttackTime, &event_buffer)),
+            attack_time: attack_time_param()
+                .with_callback(generate_callback(AttackTime, &event_buffer)),
 
             // RELEASE
-            release_time: FloatParam::new(
-                "Release Time",
-                DEFAULT_RELEASE_TIME,
+            release_time: release_time_param()
+                .with_callback(generate_callback(ReleaseTime, &event_buffer)),
+            // HOLD
+            hold_time: FloatParam::new(
+                "Hold Time",
+                0.0,
                 FloatRange::Skewed {
                     min: 0.0,
-                    max: 5.0,
-                    factor: FloatRange::skew_factor(-2.25), // kinda funky but i tried
+                    max: 0.5,
+                    factor: FloatRange::skew_factor(-2.0),
                 },
             )
             .with_smoother(SmoothingStyle::Linear(10.0))
             .with_value_to_string(v2s_time_formatter())
-            .with_callback(generate_callback(ReleaseTime, &event_buffer)),
+            .with_callback(generate_callback(HoldTime, &event_buffer)),
+            // AUTO RELEASE
+            auto_release: FloatParam::new(
+                "Auto Release",
+                0.0,
+                FloatRange::Linear { min: 0.0, max: 1.0 },
+            )
+            .with_smoother(SmoothingStyle::Linear(10.0))
+            .with_value_to_string(v2s_rounded_multiplied(1)),
             // KNEE WIDTH
-            knee_width: FloatParam::new(
-                "Knee Width",
-                DEFAULT_KNEE,
+            knee_width: knee_width_param()
+                .with_callback(generate_callback(KneeWidth, &event_buffer)),
+            // EXPANSION / UPWARD COMPRESSION
+            expansion_ratio: dynamics_ratio_param("Expansion Ratio", 100.0),
+            expansion_range: dynamics_range_param(
+                "Expansion Range",
+                DEFAULT_EXPANSION_RANGE,
+                100.0,
+            ),
+            upward_compression_ratio: dynamics_ratio_param("Upward Ratio", 20.0),
+            upward_compression_range: dynamics_range_param(
+                "Upward Range",
+                DEFAULT_UPWARD_RANGE,
+                30.0,
+            ),
+            upward_expansion_ratio: dynamics_ratio_param("Upward Expansion Ratio", 20.0),
+            upward_expansion_range: dynamics_range_param(
+                "Upward Expansion Range",
+                DEFAULT_UPWARD_RANGE,
+                30.0,
+            ),
+            // RANGE
+            range: FloatParam::new(
+                "Range",
+                DEFAULT_RANGE,
                 FloatRange::Linear {
                     min: 0.0,
-                    max: 20.0,
+                    max: MAX_RANGE,
                 },
             )
             .with_smoother(SmoothingStyle::Linear(10.0))
-            .with_unit(" dB")
-            .with_value_to_string(v2s_f32_rounded(1))
-            .with_callback(generate_callback(KneeWidth, &event_buffer)),
+            .with_value_to_string(v2s_range_formatter())
+            .with_callback(generate_callback(Range, &event_buffer)),
             // INPUT GAIN
             // basically, the exact same as this. LOL
             // https://github.com/robbert-vdh/nih-plug/blob/ffe9b61fcb0441c9d33f4413f5ebe7394637b21f/plugins/examples/gain/src/lib.rs#L67
@@ -318,11 +872,83 @@ impl Default for CompressorParams {
             .with_string_to_value(formatters::s2v_f32_gain_to_db())
             .with_callback(generate_callback(OutputGain, &event_buffer)),
 
+            // AUTO MAKEUP
+            makeup_mode: EnumParam::new("Auto Makeup", MakeupMode::Off),
+
             // DRY/WET
             dry_wet: FloatParam::new("Dry/Wet", 1.0, FloatRange::Linear { min: 0.0, max: 1.0 }) // 1.0 default for full compressor effect
                 .with_smoother(SmoothingStyle::Linear(10.0))
                 .with_value_to_string(v2s_rounded_multiplied(1))
                 .with_callback(generate_callback(DryWet, &event_buffer)),
+            blend_mode: EnumParam::new("Blend", BlendMode::Linear),
+            wet_tilt: FloatParam::new(
+                "Wet Tilt",
+                0.0,
+                FloatRange::Linear {
+                    min: -12.0,
+                    max: 12.0,
+                },
+            )
+            .with_smoother(SmoothingStyle::Linear(10.0))
+            .with_unit(" dB")
+            .with_value_to_string(v2s_f32_rounded(1)),
+            wet_gain: FloatParam::new(
+                "Wet Gain",
+                0.0,
+                FloatRange::Linear {
+                    min: -24.0,
+                    max: 24.0,
+                },
+            )
+            .with_smoother(SmoothingStyle::Linear(10.0))
+            .with_unit(" dB")
+            .with_value_to_string(v2s_f32_rounded(1)),
+            // SATURATION
+            saturation_position: EnumParam::new("Saturation", SaturationPosition::Off),
+            saturation_type: EnumParam::new("Saturation Type", SaturationType::Tape),
+            saturation_drive: FloatParam::new(
+                "Drive",
+                6.0,
+                FloatRange::Linear {
+                    min: 0.0,
+                    max: 24.0,
+                },
+            )
+            .with_smoother(SmoothingStyle::Linear(10.0))
+            .with_unit(" dB")
+            .with_value_to_string(v2s_f32_rounded(1)),
+            saturation_mix: FloatParam::new(
+                "Saturation Mix",
+                1.0,
+                FloatRange::Linear { min: 0.0, max: 1.0 },
+            )
+            .with_smoother(SmoothingStyle::Linear(10.0))
+            .with_value_to_string(v2s_rounded_multiplied(1)),
+
+            // LIMITER
+            limiter_enabled: BoolParam::new("Limiter", false),
+            limiter_ceiling: FloatParam::new(
+                "Ceiling",
+                -1.0,
+                FloatRange::Linear {
+                    min: -30.0,
+                    max: 0.0,
+                },
+            )
+            .with_smoother(SmoothingStyle::Linear(10.0))
+            .with_unit(" dB")
+            .with_value_to_string(v2s_f32_rounded(1)),
+            limiter_release: FloatParam::new(
+                "Limiter Release",
+                0.05,
+                FloatRange::Skewed {
+                    min: 0.001,
+                    max: 1.0,
+                    factor: FloatRange::skew_factor(-2.0),
+                },
+            )
+            .with_smoother(SmoothingStyle::Linear(10.0))
+            .with_value_to_string(v2s_time_formatter()),
             // RMS BUFFER SIZE
             rms_buffer_size: FloatParam::new(
                 "RMS Size",
@@ -337,13 +963,16 @@ impl Default for CompressorParams {
             .with_callback(generate_callback(RmsBufferSize, &event_buffer))
             .with_callback(rms_callback),
 
+            // OVERSAMPLING
+            oversampling: EnumParam::new("Oversampling", Oversampling::X1),
+
             // LOOKEAHEAD
             lookahead: FloatParam::new(
                 "Lookahead",
                 0.0,
                 FloatRange::Linear {
                     min: 0.0,
-                    max: MAX_BUFFER_SIZE,
+                    max: MAX_LOOKAHEAD,
                 },
             )
             .with_value_to_string(v2s_buffer_size_formatter())
@@ -356,11 +985,230 @@ impl Default for CompressorParams {
                 .with_smoother(SmoothingStyle::Linear(10.0))
                 .with_callback(generate_callback(RmsMix, &event_buffer)),
 
+            // SIDECHAIN SOURCE
+            sidechain_source: EnumParam::new("Sidechain", SidechainSource::Internal),
+            // SIDECHAIN FILTER
+            sidechain_filter_type: EnumParam::new("Sidechain Filter", SidechainFilterType::Off),
+            sidechain_filter_frequency: crossover_param("Sidechain Frequency", 100.0),
+            sidechain_filter_q: FloatParam::new(
+                "Sidechain Q",
+                std::f32::consts::FRAC_1_SQRT_2,
+                FloatRange::Skewed {
+                    min: 0.1,
+                    max: 10.0,
+                    factor: FloatRange::skew_factor(-2.0),
+                },
+            )
+            .with_smoother(SmoothingStyle::Linear(10.0))
+            .with_value_to_string(v2s_f32_rounded(2)),
+            sidechain_listen: BoolParam::new("Sidechain Listen", false),
+
+            // DE-ESSER
+            de_esser_mode: EnumParam::new("De-Esser", DeEsserMode::Off),
+            de_esser_frequency: crossover_param("De-Esser Frequency", DEFAULT_DE_ESSER_FREQUENCY)
+                .with_callback(generate_callback(DeEsserFrequency, &event_buffer)),
+            de_esser_listen: BoolParam::new("De-Esser Listen", false)
+                .with_callback(generate_callback(DeEsserListen, &event_buffer)),
+            // LEVEL DETECTION
+            level_detection_type: EnumParam::new("Detector", LevelDetectionType::Rms),
+            // TOPOLOGY
+            topology: EnumParam::new("Topology", Topology::FeedForward),
+            // ENVELOPE
+            envelope_type: EnumParam::new("Envelope", EnvelopeType::Level),
+            // CHARACTER
+            character: EnumParam::new("Character", Character::Vca),
+            // TRANSIENT SHAPER
+            transient_shaper: BoolParam::new("Transient Shaper", false)
+                .with_callback(generate_callback(TransientShaper, &event_buffer)),
+            attack_emphasis: emphasis_param("Attack Emphasis")
+                .with_callback(generate_callback(AttackEmphasis, &event_buffer)),
+            sustain_emphasis: emphasis_param("Sustain Emphasis")
+                .with_callback(generate_callback(SustainEmphasis, &event_buffer)),
+
+            // MID/SIDE
+            stereo_mode: EnumParam::new("Stereo Mode", StereoMode::LeftRight),
+            mid_threshold_offset: threshold_offset_param("Mid Threshold Offset"),
+            mid_ratio_offset: ratio_offset_param("Mid Ratio Offset"),
+            side_threshold_offset: threshold_offset_param("Side Threshold Offset"),
+            side_ratio_offset: ratio_offset_param("Side Ratio Offset"),
+
+            // MULTIBAND
+            num_bands: IntParam::new("Bands", 1, IntRange::Linear { min: 1, max: 4 }),
+            crossover_1: crossover_param("Crossover 1", DEFAULT_CROSSOVERS[0]),
+            crossover_2: crossover_param("Crossover 2", DEFAULT_CROSSOVERS[1]),
+            crossover_3: crossover_param("Crossover 3", DEFAULT_CROSSOVERS[2]),
+            bands: Default::default(),
+
             rms_update,
             event_buffer,
         }
     }
 }
+/// Creates a threshold parameter. The main controls and every band use this, so their ranges always match.
+fn threshold_param() -> FloatParam {
+    FloatParam::new(
+        "Threshold",
+        DEFAULT_THRESHOLD,
+        FloatRange::Skewed {
+            min: -100.0,
+            max: 5.0,
+            factor: FloatRange::skew_factor(2.25),
+        },
+    )
+    // our threshold is already in dB land, so we don't need any conversion/formatting
+    // TODO: play with smoothing style/timing
+    .with_smoother(SmoothingStyle::Linear(10.0))
+    .with_unit(" dB")
+    // TODO:
+    // create a custom formatter for -inf dB
+    .with_value_to_string(formatters::v2s_f32_rounded(2))
+}
+
+/// Creates a (downward compression) ratio parameter, see `threshold_param()`.
+fn ratio_param() -> FloatParam {
+    FloatParam::new(
+        "Ratio",
+        DEFAULT_RATIO, // default compression ratio of 4:1 dB
+        FloatRange::Skewed {
+            min: 1.0,
+            max: 100.0,
+            factor: FloatRange::skew_factor(-1.8),
+        },
+    )
+    .with_smoother(SmoothingStyle::Linear(10.0))
+    // TODO: customize formatter
+    .with_value_to_string(formatters::v2s_compression_ratio(2))
+    .with_unit(" dB")
+}
+
+/// Creates an attack time parameter, see `threshold_param()`.
+fn attack_time_param() -> FloatParam {
+    FloatParam::new(
+        "Attack Time",
+        DEFAULT_ATTACK_TIME,
+        FloatRange::Skewed {
+            min: 0.0, // 0 seconds atk time, meaning the compressor takes effect instantly
+            max: 1.0,
+            factor: FloatRange::skew_factor(-2.0), // just happened to be right in the middle
+        },
+    )
+    .with_smoother(SmoothingStyle::Linear(10.0))
+    .with_value_to_string(v2s_time_formatter())
+}
+
+/// Creates a release time parameter, see `threshold_param()`.
+fn release_time_param() -> FloatParam {
+    FloatParam::new(
+        "Release Time",
+        DEFAULT_RELEASE_TIME,
+        FloatRange::Skewed {
+            min: 0.0,
+            max: 5.0,
+            factor: FloatRange::skew_factor(-2.25), // kinda funky but i tried
+        },
+    )
+    .with_smoother(SmoothingStyle::Linear(10.0))
+    .with_value_to_string(v2s_time_formatter())
+}
+
+/// Creates a knee width parameter, see `threshold_param()`.
+fn knee_width_param() -> FloatParam {
+    FloatParam::new(
+        "Knee Width",
+        DEFAULT_KNEE,
+        FloatRange::Linear {
+            min: 0.0,
+            max: 20.0,
+        },
+    )
+    .with_smoother(SmoothingStyle::Linear(10.0))
+    .with_unit(" dB")
+    .with_value_to_string(v2s_f32_rounded(1))
+}
+
+/// Creates a ratio parameter for one of the expansion/upward parts of the curve. These are off (`1.0`) by default.
+fn dynamics_ratio_param(name: &str, max: f32) -> FloatParam {
+    FloatParam::new(
+        name,
+        1.0,
+        FloatRange::Skewed {
+            min: 1.0,
+            max,
+            factor: FloatRange::skew_factor(-1.8),
+        },
+    )
+    .with_smoother(SmoothingStyle::Linear(10.0))
+    .with_value_to_string(formatters::v2s_f32_rounded(2))
+}
+
+/// Creates a range parameter (**in decibels**) for one of the expansion/upward parts of the curve.
+fn dynamics_range_param(name: &str, default: f32, max: f32) -> FloatParam {
+    FloatParam::new(name, default, FloatRange::Linear { min: 0.0, max })
+        .with_smoother(SmoothingStyle::Linear(10.0))
+        .with_unit(" dB")
+        .with_value_to_string(v2s_f32_rounded(1))
+}
+
+/// Creates an attack or sustain emphasis parameter for the transient shaper.
+fn emphasis_param(name: &str) -> FloatParam {
+    FloatParam::new(
+        name,
+        0.0,
+        FloatRange::Linear {
+            min: -12.0,
+            max: 12.0,
+        },
+    )
+    .with_smoother(SmoothingStyle::Linear(10.0))
+    .with_unit(" dB")
+    .with_value_to_string(v2s_f32_rounded(1))
+}
+
+/// Creates a threshold offset parameter for the mid or side channel.
+fn threshold_offset_param(name: &str) -> FloatParam {
+    FloatParam::new(
+        name,
+        0.0,
+        FloatRange::Linear {
+            min: -30.0,
+            max: 30.0,
+        },
+    )
+    .with_smoother(SmoothingStyle::Linear(10.0))
+    .with_unit(" dB")
+    .with_value_to_string(v2s_f32_rounded(1))
+}
+
+/// Creates a ratio offset parameter for the mid or side channel.
+fn ratio_offset_param(name: &str) -> FloatParam {
+    FloatParam::new(
+        name,
+        0.0,
+        FloatRange::Linear {
+            min: -20.0,
+            max: 20.0,
+        },
+    )
+    .with_smoother(SmoothingStyle::Linear(10.0))
+    .with_value_to_string(v2s_f32_rounded(2))
+}
+
+/// Creates a crossover frequency parameter, since all of them are the same apart from the name and default.
+fn crossover_param(name: &str, default: f32) -> FloatParam {
+    FloatParam::new(
+        name,
+        default,
+        FloatRange::Skewed {
+            min: 20.0,
+            max: 20_000.0,
+            factor: FloatRange::skew_factor(-2.0),
+        },
+    )
+    .with_smoother(SmoothingStyle::Logarithmic(50.0))
+    .with_value_to_string(formatters::v2s_f32_hz_then_khz(0))
+    .with_string_to_value(formatters::s2v_f32_hz_then_khz())
+}
+
 // very slightly modified NIH-plug formatter
 
 pub fn v2s_rounded_multiplied(digits: usize) -> Arc<dyn Fn(f32) -> String + Send + Sync> {
@@ -391,6 +1239,17 @@ pub fn v2s_time_formatter() -> Arc<dyn Fn(f32) -> String + Send + Sync> {
     })
 }
 
+pub fn v2s_range_formatter() -> Arc<dyn Fn(f32) -> String + Send + Sync> {
+    Arc::new(move |value| {
+        // the top of the range doesn't limit anything
+        if value >= MAX_RANGE {
+            String::from("Unlimited")
+        } else {
+            format!("{value:.1} dB")
+        }
+    })
+}
+
 pub fn v2s_buffer_size_formatter() -> Arc<dyn Fn(f32) -> String + Send + Sync> {
     Arc::new(move |value| {
         // from S to MS
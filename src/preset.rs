@@ -0,0 +1,298 @@
+//! JSON presets for the offline tools (`render` and `plot`).
+//!
+//! A preset holds the same things as the plugin's parameters, so running one through a `Processor` sounds just like the plugin would.
+//! Every field is optional, anything that's missing uses the plugin's default. For example:
+//! ```json
+//! {
+//!     "threshold": -20.0,
+//!     "ratio": 8.0,
+//!     "level_detection_type": "true-peak",
+//!     "rms_buffer_size": 0.02,
+//!     "stereo_mode": "mid-side",
+//!     "multiband": { "num_bands": 2, "crossovers": [200.0, 1000.0, 6000.0] },
+//!     "limiter_enabled": true
+//! }
+//! ```
+
+use std::{error::Error, fs::File, io::BufReader, path::Path};
+
+use nih_plug::util;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    dsp::{
+        BandSettings, Character, CompressorSettings, DeEsserSettings, DynamicsCurve, EnvelopeType,
+        LevelDetectionType, MultibandSettings, ParallelSettings, SaturationSettings,
+        SidechainFilterSettings, Topology, MAX_BANDS,
+    },
+    params::{
+        MakeupMode, Oversampling, SidechainSource, StereoMode, DEFAULT_ATTACK_TIME,
+        DEFAULT_BUFFER_SIZE, DEFAULT_CROSSOVERS, DEFAULT_KNEE, DEFAULT_RATIO, DEFAULT_RELEASE_TIME,
+        DEFAULT_THRESHOLD,
+    },
+    processor::ProcessorSettings,
+    MAX_BUFFER_SIZE, MAX_LOOKAHEAD,
+};
+
+/// Everything needed to process a file. See `CompressorParams` for what each of these does.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Preset {
+    #[serde(flatten)]
+    pub settings: CompressorSettings,
+    /// The RMS window length **in seconds**. This lives here because the plugin handles it, not the `Compressor`.
+    pub rms_buffer_size: f32,
+    pub oversampling: Oversampling,
+    pub multiband: MultibandPreset,
+    pub sidechain_source: SidechainSource,
+    pub sidechain_filter: SidechainFilterSettings,
+    pub sidechain_listen: bool,
+    pub de_esser: DeEsserSettings,
+    pub de_esser_listen: bool,
+    pub saturation: SaturationSettings,
+    pub parallel: ParallelSettings,
+    pub stereo_mode: StereoMode,
+    /// The `[threshold, ratio]` offsets for the mid and side channels, in that order.
+    pub mid_side_offsets: [(f32, f32); 2],
+    pub makeup_mode: MakeupMode,
+    /// **In decibels.**
+    pub input_gain: f32,
+    /// **In decibels.**
+    pub output_gain: f32,
+    pub limiter_enabled: bool,
+    /// **In decibels.**
+    pub limiter_ceiling: f32,
+    /// **In seconds.**
+    pub limiter_release: f32,
+}
+impl Default for Preset {
+    fn default() -> Self {
+        Self {
+            settings: CompressorSettings::default(),
+            rms_buffer_size: DEFAULT_BUFFER_SIZE,
+            oversampling: Oversampling::X1,
+            multiband: MultibandPreset::default(),
+            sidechain_source: SidechainSource::Internal,
+            sidechain_filter: SidechainFilterSettings::default(),
+            sidechain_listen: false,
+            de_esser: DeEsserSettings::default(),
+            de_esser_listen: false,
+            saturation: SaturationSettings::default(),
+            parallel: ParallelSettings::default(),
+            stereo_mode: StereoMode::LeftRight,
+            mid_side_offsets: [(0.0, 0.0); 2],
+            makeup_mode: MakeupMode::Off,
+            input_gain: 0.0,
+            output_gain: 0.0,
+            limiter_enabled: false,
+            limiter_ceiling: -1.0,
+            limiter_release: 0.05,
+        }
+    }
+}
+impl Preset {
+    /// Reads a preset from a JSON file (or starts from the defaults without one), applies the `overrides` on top of it,
+    /// and then clamps the result. This is how both of the offline tools load their settings.
+    pub fn load(path: Option<&Path>, overrides: &Overrides) -> Result<Self, Box<dyn Error>> {
+        let mut preset: Self = match path {
+            Some(path) => serde_json::from_reader(BufReader::new(File::open(path)?))?,
+            None => Self::default(),
+        };
+        overrides.apply(&mut preset);
+        // the plugin's parameter ranges make sure of this, but here anything goes.
+        // this comes last, so the overrides can't get around it
+        preset.clamp();
+        Ok(preset)
+    }
+    /// Keeps everything that the plugin's parameter ranges would keep in range, since anything goes in a file.
+    pub fn clamp(&mut self) {
+        self.rms_buffer_size = self.rms_buffer_size.clamp(0.0, MAX_BUFFER_SIZE);
+        self.settings.lookahead = self.settings.lookahead.clamp(0.0, MAX_LOOKAHEAD);
+        self.multiband.num_bands = self.multiband.num_bands.clamp(1, MAX_BANDS);
+    }
+    /// The settings for a `Processor` running frames of `num_channels` channels.
+    /// `CompressorParams::processor_settings()` goes through this too, so a preset sounds just like the plugin.
+    pub fn processor_settings(&self, num_channels: usize) -> ProcessorSettings {
+        ProcessorSettings {
+            compressor: self.settings,
+            multiband: self.multiband.multiband_settings(&self.settings),
+            sidechain_source: self.sidechain_source,
+            sidechain_filter: self.sidechain_filter,
+            sidechain_listen: self.sidechain_listen,
+            de_esser: self.de_esser,
+            de_esser_listen: self.de_esser_listen,
+            saturation: self.saturation,
+            parallel: self.parallel,
+            // mid/side only makes sense with 2 channels
+            stereo_mode: if num_channels == 2 {
+                self.stereo_mode
+            } else {
+                StereoMode::LeftRight
+            },
+            mid_side_offsets: self.mid_side_offsets,
+            makeup_mode: self.makeup_mode,
+            input_gain: util::db_to_gain(self.input_gain),
+            output_gain: util::db_to_gain(self.output_gain),
+            limiter_enabled: self.limiter_enabled,
+            limiter_ceiling: self.limiter_ceiling,
+            limiter_release: self.limiter_release,
+        }
+    }
+}
+
+/// The multiband controls of a `Preset`. Like in the plugin, each band only has its own threshold, ratio, knee, attack and release,
+/// and takes everything else from the main settings.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MultibandPreset {
+    /// From 1 to `MAX_BANDS`. With 1 band, the full-band compressor is used instead.
+    pub num_bands: usize,
+    /// **In Hz**, from low to high.
+    pub crossovers: [f32; MAX_BANDS - 1],
+    pub bands: [BandPreset; MAX_BANDS],
+}
+impl Default for MultibandPreset {
+    fn default() -> Self {
+        Self {
+            num_bands: 1,
+            crossovers: DEFAULT_CROSSOVERS,
+            bands: Default::default(),
+        }
+    }
+}
+impl MultibandPreset {
+    /// The settings for the `MultibandCompressor`. Anything that isn't a per-band control comes from `settings`.
+    pub fn multiband_settings(&self, settings: &CompressorSettings) -> MultibandSettings {
+        let mut crossovers = self.crossovers;
+        // the crossovers can't overlap, so each one has to be at least as high as the one below it
+        for i in 1..crossovers.len() {
+            crossovers[i] = crossovers[i].max(crossovers[i - 1]);
+        }
+
+        let bands = self.bands.map(|band| BandSettings {
+            compressor: CompressorSettings {
+                curve: DynamicsCurve {
+                    threshold: band.threshold,
+                    ratio: band.ratio,
+                    knee_width: band.knee_width,
+                    ..settings.curve
+                },
+                attack_time: band.attack_time,
+                release_time: band.release_time,
+                ..*settings
+            },
+            solo: band.solo,
+            bypass: band.bypass,
+        });
+
+        MultibandSettings {
+            num_bands: self.num_bands,
+            crossovers,
+            bands,
+        }
+    }
+}
+
+/// The controls for a single band of a `MultibandPreset`. See `BandParams`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BandPreset {
+    /// **In decibels.**
+    pub threshold: f32,
+    pub ratio: f32,
+    /// **In decibels.**
+    pub knee_width: f32,
+    /// **In seconds.**
+    pub attack_time: f32,
+    /// **In seconds.**
+    pub release_time: f32,
+    pub solo: bool,
+    pub bypass: bool,
+}
+impl Default for BandPreset {
+    fn default() -> Self {
+        Self {
+            threshold: DEFAULT_THRESHOLD,
+            ratio: DEFAULT_RATIO,
+            knee_width: DEFAULT_KNEE,
+            attack_time: DEFAULT_ATTACK_TIME,
+            release_time: DEFAULT_RELEASE_TIME,
+            solo: false,
+            bypass: false,
+        }
+    }
+}
+
+/// Settings that replace whatever a `Preset` says, like the offline tools' command line flags. Anything that's `None` is left alone.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Overrides {
+    /// **In decibels.**
+    pub threshold: Option<f32>,
+    pub ratio: Option<f32>,
+    /// **In decibels.**
+    pub knee_width: Option<f32>,
+    /// **In seconds.**
+    pub attack_time: Option<f32>,
+    /// **In seconds.**
+    pub release_time: Option<f32>,
+    /// **In seconds.**
+    pub hold_time: Option<f32>,
+    pub auto_release: Option<f32>,
+    /// **In seconds.**
+    pub lookahead: Option<f32>,
+    /// **In seconds.**
+    pub rms_buffer_size: Option<f32>,
+    pub rms_mix: Option<f32>,
+    pub level_detection_type: Option<LevelDetectionType>,
+    pub topology: Option<Topology>,
+    pub envelope_type: Option<EnvelopeType>,
+    pub character: Option<Character>,
+}
+impl Overrides {
+    /// Replaces everything in `preset` that has been overridden. This doesn't clamp anything, see `Preset::load()`.
+    pub fn apply(&self, preset: &mut Preset) {
+        let settings = &mut preset.settings;
+        if let Some(threshold) = self.threshold {
+            settings.curve.threshold = threshold;
+        }
+        if let Some(ratio) = self.ratio {
+            settings.curve.ratio = ratio;
+        }
+        if let Some(knee_width) = self.knee_width {
+            settings.curve.knee_width = knee_width;
+        }
+        if let Some(attack_time) = self.attack_time {
+            settings.attack_time = attack_time;
+        }
+        if let Some(release_time) = self.release_time {
+            settings.release_time = release_time;
+        }
+        if let Some(hold_time) = self.hold_time {
+            settings.hold_time = hold_time;
+        }
+        if let Some(auto_release) = self.auto_release {
+            settings.auto_release = auto_release;
+        }
+        if let Some(lookahead) = self.lookahead {
+            settings.lookahead = lookahead;
+        }
+        if let Some(rms_mix) = self.rms_mix {
+            settings.rms_mix = rms_mix;
+        }
+        if let Some(level_detection_type) = self.level_detection_type {
+            settings.level_detection_type = level_detection_type;
+        }
+        if let Some(topology) = self.topology {
+            settings.topology = topology;
+        }
+        if let Some(envelope_type) = self.envelope_type {
+            settings.envelope_type = envelope_type;
+        }
+        if let Some(character) = self.character {
+            settings.character = character;
+        }
+        if let Some(rms_buffer_size) = self.rms_buffer_size {
+            preset.rms_buffer_size = rms_buffer_size;
+        }
+    }
+}
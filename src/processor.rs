@@ -0,0 +1,900 @@
+//! The whole signal chain, from the input (and the key) to the output, for up to 2 channels.
+//! This lives outside of `CompressorPlugin` so that the `render` tool can run exactly the same chain without a host.
+
+use nih_plug::util;
+
+use crate::{
+    dsp::{
+        calculate_filter_coefficient, link_levels, Compressor, CompressorSettings, DeEsserMode,
+        DeEsserSettings, DelayLine, Downsampler, GainReductionAverage, Limiter,
+        MultibandCompressor, MultibandSettings, ParallelPath, ParallelSettings, RmsLevelDetector,
+        SaturationPosition, SaturationSettings, Saturator, SidechainFilter,
+        SidechainFilterSettings, SplitBandDeEsser, Upsampler, MAX_BANDS, MAX_OVERSAMPLING,
+    },
+    params::{MakeupMode, SidechainSource, StereoMode, DEFAULT_BUFFER_SIZE},
+    AUTO_MAKEUP_TIME, LIMITER_LOOKAHEAD, MAX_BUFFER_SIZE, MAX_LOOKAHEAD,
+};
+
+/// Everything the `Processor` needs to know to process a single frame. See `CompressorParams` for what each of these does.
+#[derive(Debug, Clone, Copy)]
+pub struct ProcessorSettings {
+    pub compressor: CompressorSettings,
+    /// Only used when there's more than 1 band (and the de-esser is off).
+    pub multiband: MultibandSettings,
+    pub sidechain_source: SidechainSource,
+    pub sidechain_filter: SidechainFilterSettings,
+    pub sidechain_listen: bool,
+    pub de_esser: DeEsserSettings,
+    pub de_esser_listen: bool,
+    pub saturation: SaturationSettings,
+    pub parallel: ParallelSettings,
+    /// This has to be `StereoMode::LeftRight` unless there are exactly 2 channels.
+    pub stereo_mode: StereoMode,
+    /// The `(threshold, ratio)` offsets for the mid and side channels, in that order.
+    pub mid_side_offsets: [(f32, f32); 2],
+    pub makeup_mode: MakeupMode,
+    /// **In linear space.**
+    pub input_gain: f32,
+    /// **In linear space.**
+    pub output_gain: f32,
+    pub limiter_enabled: bool,
+    /// **In decibels.**
+    pub limiter_ceiling: f32,
+    /// **In seconds.**
+    pub limiter_release: f32,
+}
+
+/// What the meters should show after a single frame.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct FrameMeters {
+    /// The average of the dry signal (after the input gain) over every channel.
+    pub pre_amplitude: f32,
+    /// The average of the output over every channel.
+    pub post_amplitude: f32,
+    /// The gain reduction of each channel **in linear space**. A channel that isn't compressed reads `1.0`.
+    pub amount_reduced: [f32; 2],
+    /// The gain reduction of each band **in linear space**, averaged over every channel.
+    pub band_reduced: [f32; MAX_BANDS],
+}
+
+/// Runs frames of 1 or 2 channels through the key selection, sidechain filter, M/S encoding, oversampling,
+/// compressors (or de-esser), saturation, parallel path, makeup gain, dry/wet, output gain and limiter, in that order.
+pub struct Processor {
+    sample_rate: f32,
+    compressors: [Compressor; 2],
+    /// One for each channel's key signal.
+    sidechain_filters: [SidechainFilter; 2],
+    /// One for each channel, see `SaturationPosition` for where these go.
+    saturators: [Saturator; 2],
+    /// Used for `DeEsserMode::SplitBand`, one for each channel.
+    split_band_de_essers: [SplitBandDeEsser; 2],
+    /// The wet tilt and gain for parallel compression, one for each channel.
+    parallel_paths: [ParallelPath; 2],
+    /// Used instead of `compressors` when there's more than 1 band.
+    multiband_compressors: [MultibandCompressor; 2],
+    /// Used for `MakeupMode::Average`.
+    gain_reduction_average: GainReductionAverage,
+    /// The current oversampling factor (1 means no oversampling).
+    oversampling: usize,
+    /// The current RMS window length **in seconds**.
+    rms_buffer_size: f32,
+    /// Upsample the audio and the key for each channel, before they go into the compressors.
+    upsamplers: [Upsampler; 2],
+    key_upsamplers: [Upsampler; 2],
+    /// Bring the dry (but delayed) and compressed signals back down to the original sample rate.
+    /// The dry signal goes through the same filters, so that it still lines up with the compressed signal.
+    dry_downsamplers: [Downsampler; 2],
+    wet_downsamplers: [Downsampler; 2],
+    /// The brickwall limiter at the very end, shared by both channels.
+    limiter: Limiter,
+    /// Whether the limiter ran on the last frame. It isn't fed while it's off, so it has to be reset when it comes back on.
+    limiting: bool,
+    /// How many bands the multiband compressors ran with on the last frame, or 1 if the full-band compressors ran instead.
+    /// Only one of them runs at a time, so whichever one takes over has to be reset first.
+    active_bands: usize,
+}
+
+impl Default for Processor {
+    fn default() -> Self {
+        Self {
+            // this doesn't really matter, as long as we go through new() before processing anything
+            sample_rate: 0.0,
+            compressors: [Compressor::new(), Compressor::new()],
+            sidechain_filters: Default::default(),
+            saturators: Default::default(),
+            split_band_de_essers: Default::default(),
+            parallel_paths: Default::default(),
+            multiband_compressors: Default::default(),
+            gain_reduction_average: GainReductionAverage::default(),
+            oversampling: 1,
+            rms_buffer_size: DEFAULT_BUFFER_SIZE,
+            upsamplers: Default::default(),
+            key_upsamplers: Default::default(),
+            dry_downsamplers: Default::default(),
+            wet_downsamplers: Default::default(),
+            limiter: Limiter::default(),
+            limiting: false,
+            active_bands: 1,
+        }
+    }
+}
+
+impl Processor {
+    /// Construct a new `Processor` for the given sample rate. This allocates everything up front,
+    /// so nothing else (including `set_oversampling()` and `set_rms_buffer_size()`) has to.
+    pub fn new(sample_rate: f32) -> Self {
+        let mut processor = Self {
+            sample_rate,
+            upsamplers: std::array::from_fn(|_| Upsampler::new(MAX_OVERSAMPLING)),
+            key_upsamplers: std::array::from_fn(|_| Upsampler::new(MAX_OVERSAMPLING)),
+            dry_downsamplers: std::array::from_fn(|_| Downsampler::new(MAX_OVERSAMPLING)),
+            wet_downsamplers: std::array::from_fn(|_| Downsampler::new(MAX_OVERSAMPLING)),
+            limiter: Limiter::new((sample_rate * LIMITER_LOOKAHEAD) as usize),
+            ..Default::default()
+        };
+        processor.initialize_rms_buffers();
+        processor.initialize_delay_lines();
+        processor.update_rms_length();
+        processor
+    }
+    fn initialize_rms_buffers(&mut self) {
+        let max_buffer_length = (self.sample_rate * MAX_BUFFER_SIZE) as usize * MAX_OVERSAMPLING;
+
+        for compressor in self.all_compressors() {
+            compressor.detectors.rms = RmsLevelDetector::new(max_buffer_length);
+        }
+    }
+    fn initialize_delay_lines(&mut self) {
+        let max_delay = (self.sample_rate * MAX_LOOKAHEAD) as usize * MAX_OVERSAMPLING;
+
+        for compressor in self.all_compressors() {
+            compressor.delay_line = DelayLine::new(max_delay);
+        }
+    }
+    /// Every `Compressor` the processor owns, both the full-band ones and the ones for each band.
+    fn all_compressors(&mut self) -> impl Iterator<Item = &mut Compressor> {
+        self.compressors.iter_mut().chain(
+            self.multiband_compressors
+                .iter_mut()
+                .flat_map(|multiband| multiband.compressors.iter_mut()),
+        )
+    }
+    /// The full-band `Compressor` for each channel, e.g. to look at its envelope.
+    /// These aren't used in multiband mode.
+    pub fn compressors(&self) -> &[Compressor; 2] {
+        &self.compressors
+    }
+    /// The latency introduced by lookahead (**in seconds**), oversampling (and the limiter, if it's on), in samples.
+    pub fn latency(&self, lookahead: f32, limiter_enabled: bool) -> u32 {
+        let lookahead = self.lookahead_samples(lookahead);
+        let oversampling = Downsampler::latency(self.oversampling);
+        if limiter_enabled {
+            lookahead + oversampling + self.limiter.latency()
+        } else {
+            lookahead + oversampling
+        }
+    }
+    /// How far the `amount_reduced` in `FrameMeters` lags behind the input, in samples.
+    /// The gain reduction for a sample is known once it comes out of the lookahead delay,
+    /// which is before the downsampler and the limiter, so only the lookahead and the `Upsampler` count here.
+    pub fn meter_latency(&self, lookahead: f32) -> u32 {
+        let lookahead = self.lookahead_samples(lookahead);
+        lookahead + Upsampler::latency(self.oversampling).round() as u32
+    }
+    /// The lookahead (**in seconds**) in whole samples at the original sample rate.
+    /// The compressors get exactly this many samples (times the oversampling factor), so that it always matches `latency()`.
+    fn lookahead_samples(&self, lookahead: f32) -> u32 {
+        (self.sample_rate * lookahead).round() as u32
+    }
+    /// The sample rate that the compressors run at, after oversampling.
+    fn oversampled_rate(&self) -> f32 {
+        self.sample_rate * self.oversampling as f32
+    }
+    /// Switches the oversampling factor, which has to be between 1 and `MAX_OVERSAMPLING`.
+    /// This doesn't allocate, so it's fine to call from `process()`.
+    pub fn set_oversampling(&mut self, oversampling: usize) {
+        if oversampling == self.oversampling {
+            return;
+        }
+        self.oversampling = oversampling;
+        // the compressors now run at a different rate, so their RMS windows have to follow
+        self.update_rms_length();
+        for upsampler in self.upsamplers.iter_mut().chain(&mut self.key_upsamplers) {
+            upsampler.reset();
+        }
+        for downsampler in self
+            .dry_downsamplers
+            .iter_mut()
+            .chain(&mut self.wet_downsamplers)
+        {
+            downsampler.reset();
+        }
+    }
+    /// Sets the RMS window length **in seconds**. This doesn't allocate, so it's fine to call from `process()`.
+    pub fn set_rms_buffer_size(&mut self, rms_buffer_size: f32) {
+        self.rms_buffer_size = rms_buffer_size;
+        self.update_rms_length();
+    }
+    /// Sets the RMS windows to `rms_buffer_size`, at the oversampled rate.
+    fn update_rms_length(&mut self) {
+        let new_size = (self.oversampled_rate() * self.rms_buffer_size) as usize;
+        for compressor in self.all_compressors() {
+            compressor.detectors.rms.set_length(new_size);
+        }
+    }
+    /// Measures the level of a single (oversampled) key sample of one channel, for each band.
+    /// The full-band compressor only uses the first band.
+    ///
+    /// `multiband_settings` should be `Some` when the multiband compressor is being used.
+    fn detect_sample(
+        &mut self,
+        channel: usize,
+        key: f32,
+        settings: &CompressorSettings,
+        multiband_settings: Option<&MultibandSettings>,
+    ) -> [f32; MAX_BANDS] {
+        let sample_rate = self.oversampled_rate();
+        if let Some(settings) = multiband_settings {
+            self.multiband_compressors[channel].detect(key, settings, sample_rate)
+        } else {
+            let mut levels = [0.0; MAX_BANDS];
+            levels[0] = self.compressors[channel].detect(key, settings, sample_rate);
+            levels
+        }
+    }
+    /// Runs a single (oversampled) sample of one channel through the full-band or multiband compressor, and the de-esser.
+    /// This has to come after `detect_sample()`. `shared_levels` is what `link_levels()` made of every channel's levels.
+    /// Returns `(dry, wet, amount_reduced, band_reductions, static_makeup_db)`.
+    fn compress_sample(
+        &mut self,
+        channel: usize,
+        sample: f32,
+        shared_levels: &[f32; MAX_BANDS],
+        settings: &CompressorSettings,
+        multiband_settings: Option<&MultibandSettings>,
+        de_esser: &DeEsserSettings,
+    ) -> (f32, f32, f32, [f32; MAX_BANDS], f32) {
+        let sample_rate = self.oversampled_rate();
+        if let Some(settings) = multiband_settings {
+            let (dry, wet, band_reductions) = self.multiband_compressors[channel].process(
+                sample,
+                shared_levels,
+                settings,
+                sample_rate,
+            );
+            // the main meter shows whichever band is reducing the most
+            let amount_reduced = band_reductions.into_iter().fold(1.0, f32::min);
+            let static_makeup_db = settings.static_makeup_db();
+            (dry, wet, amount_reduced, band_reductions, static_makeup_db)
+        } else {
+            let (dry, wet, amount_reduced) =
+                self.compressors[channel].process(sample, shared_levels[0], settings, sample_rate);
+            let wet = match de_esser.mode {
+                DeEsserMode::SplitBand => self.split_band_de_essers[channel].process(
+                    dry,
+                    amount_reduced,
+                    de_esser.frequency,
+                    sample_rate,
+                ),
+                _ => wet,
+            };
+            let static_makeup_db = settings.static_makeup_db();
+            (dry, wet, amount_reduced, [1.0; MAX_BANDS], static_makeup_db)
+        }
+    }
+
+    /// Resets whatever is about to start running after being skipped, so it doesn't play out old audio from its delay lines
+    /// (or react to old envelopes and filter states). `active_bands` works just like the field of the same name.
+    fn switch_bands(&mut self, active_bands: usize) {
+        let previous_bands = self.active_bands;
+        self.active_bands = active_bands;
+        if active_bands == previous_bands {
+            return;
+        }
+        if active_bands == 1 {
+            for compressor in &mut self.compressors {
+                compressor.reset();
+            }
+            self.split_band_de_essers = Default::default();
+        } else if active_bands > previous_bands {
+            // nothing in the multiband compressors ran while the full-band ones did
+            let first_stale_band = if previous_bands == 1 {
+                0
+            } else {
+                previous_bands
+            };
+            for multiband in &mut self.multiband_compressors {
+                multiband.reset_from(first_stale_band);
+            }
+        }
+    }
+
+    /// Processes a single frame in place. `frame` has one sample for each channel, and can have 1 or 2 channels.
+    /// `aux` is the matching frame of the sidechain input, if there is one. Without it, the key is always the main input.
+    pub fn process(
+        &mut self,
+        frame: &mut [f32],
+        aux: Option<&[f32]>,
+        settings: &ProcessorSettings,
+    ) -> FrameMeters {
+        let num_channels = frame.len();
+        let oversampling = self.oversampling;
+        let de_esser = settings.de_esser;
+        let saturation = settings.saturation;
+        let parallel = settings.parallel;
+        let stereo_mode = settings.stereo_mode;
+        let multiband = settings.multiband.num_bands > 1 && !de_esser.is_enabled();
+        let active_bands = if multiband {
+            settings.multiband.num_bands.clamp(1, MAX_BANDS)
+        } else {
+            1
+        };
+        self.switch_bands(active_bands);
+
+        // first, read the input and choose what the level detection should listen to
+        let mut samples = [0.0; 2];
+        let mut keys = [0.0; 2];
+        for i in 0..num_channels {
+            let sample = frame[i];
+            let aux_sample = aux.and_then(|aux| aux.get(i)).copied();
+
+            samples[i] = sample;
+            keys[i] = match (settings.sidechain_source, aux_sample) {
+                (SidechainSource::External, Some(aux_sample)) => aux_sample,
+                (SidechainSource::Blend, Some(aux_sample)) => 0.5 * (sample + aux_sample),
+                _ => sample,
+            };
+        }
+        if stereo_mode.is_mid_side() {
+            samples = encode_mid_side(samples);
+            keys = encode_mid_side(keys);
+        }
+        // the key is filtered after the M/S encoding, so each detector hears its own (filtered) channel
+        let sidechain_filter_settings = if de_esser.is_enabled() {
+            de_esser.sidechain_filter()
+        } else {
+            settings.sidechain_filter
+        };
+        for (filter, key) in self.sidechain_filters.iter_mut().zip(&mut keys) {
+            *key = filter.process(*key, &sidechain_filter_settings, self.sample_rate);
+        }
+
+        // the detector and the gain both run at the oversampled rate, so fast gain changes don't alias
+        let lookahead =
+            self.lookahead_samples(settings.compressor.lookahead) as f32 / self.sample_rate;
+        let mut channel_settings = [settings.compressor; 2];
+        let mut channel_multiband_settings = [None; 2];
+        let mut upsampled = [[0.0; MAX_OVERSAMPLING]; 2];
+        let mut upsampled_keys = [[0.0; MAX_OVERSAMPLING]; 2];
+        for i in 0..num_channels {
+            let (threshold_offset, ratio_offset) = if stereo_mode.is_mid_side() {
+                settings.mid_side_offsets[i]
+            } else {
+                (0.0, 0.0)
+            };
+
+            channel_settings[i] = CompressorSettings {
+                lookahead,
+                ..settings
+                    .compressor
+                    .with_offsets(threshold_offset, ratio_offset)
+            };
+            channel_multiband_settings[i] = multiband.then(|| {
+                let mut multiband = settings
+                    .multiband
+                    .with_offsets(threshold_offset, ratio_offset);
+                for band in &mut multiband.bands {
+                    band.compressor.lookahead = lookahead;
+                }
+                multiband
+            });
+
+            self.upsamplers[i].process(samples[i], &mut upsampled[i][..oversampling]);
+            self.key_upsamplers[i].process(keys[i], &mut upsampled_keys[i][..oversampling]);
+        }
+
+        let mut dry = [[0.0; MAX_OVERSAMPLING]; 2];
+        let mut wet = [[0.0; MAX_OVERSAMPLING]; 2];
+        let mut amount_reduced = [0.0; 2];
+        let mut band_reductions = [[0.0; MAX_BANDS]; 2];
+        let mut static_makeup_db = [0.0; 2];
+        for j in 0..oversampling {
+            // every channel has to be measured before any of them is compressed, since the shared level needs all of them
+            let mut levels = [[0.0; MAX_BANDS]; 2];
+            for i in 0..num_channels {
+                // only the channels that are actually compressed are linked together
+                let channel_levels = self.detect_sample(
+                    i,
+                    upsampled_keys[i][j],
+                    &channel_settings[i],
+                    channel_multiband_settings[i].as_ref(),
+                );
+                if stereo_mode.compresses(i) {
+                    levels[i] = channel_levels;
+                }
+            }
+            let shared_levels: [f32; MAX_BANDS] = std::array::from_fn(|band| {
+                link_levels(&levels.map(|channel_levels| channel_levels[band]))
+            });
+
+            for i in 0..num_channels {
+                let sample = match saturation.position {
+                    SaturationPosition::Pre => self.saturators[i].process(
+                        upsampled[i][j],
+                        &saturation,
+                        self.oversampled_rate(),
+                    ),
+                    _ => upsampled[i][j],
+                };
+                let (sample_dry, sample_wet, sample_reduced, sample_band_reductions, makeup_db) =
+                    self.compress_sample(
+                        i,
+                        sample,
+                        &shared_levels,
+                        &channel_settings[i],
+                        channel_multiband_settings[i].as_ref(),
+                        &de_esser,
+                    );
+                dry[i][j] = sample_dry;
+                wet[i][j] = match saturation.position {
+                    SaturationPosition::Post => {
+                        self.saturators[i].process(sample_wet, &saturation, self.oversampled_rate())
+                    }
+                    _ => sample_wet,
+                };
+                // the meters only need one value per sample at the original rate
+                amount_reduced[i] += sample_reduced / oversampling as f32;
+                for (total, reduction) in band_reductions[i].iter_mut().zip(sample_band_reductions)
+                {
+                    *total += reduction / oversampling as f32;
+                }
+                static_makeup_db[i] = makeup_db;
+            }
+        }
+
+        let mut meters = FrameMeters::default();
+        // this loops twice, once for L/R (or M/S) channels
+        let mut pre_processed = [0.0; 2];
+        let mut processed = [0.0; 2];
+        // for the average makeup gain, only the channels that are actually compressed count
+        let mut compressed_reduced = 0.0;
+        let mut num_compressed = 0;
+        for i in 0..num_channels {
+            let dry = self.dry_downsamplers[i].process(&dry[i][..oversampling]);
+            let wet = self.wet_downsamplers[i].process(&wet[i][..oversampling]);
+
+            // the tilt shifts the phase of the wet signal, so the dry signal gets the same shift to keep them lined up.
+            // an uncompressed channel still goes through this, so that mid and side stay in phase with each other
+            let (dry, wet) = self.parallel_paths[i].process(dry, wet, &parallel, self.sample_rate);
+            pre_processed[i] = dry;
+            // even if this channel isn't compressed, it still went through the compressor so that it gets the same lookahead delay
+            if stereo_mode.compresses(i) {
+                let makeup_db = match settings.makeup_mode {
+                    MakeupMode::Off => 0.0,
+                    MakeupMode::Static => static_makeup_db[i],
+                    MakeupMode::Average => self.gain_reduction_average.makeup_db(),
+                };
+                processed[i] = wet * util::db_to_gain_fast(makeup_db);
+                meters.amount_reduced[i] = amount_reduced[i];
+                compressed_reduced += amount_reduced[i];
+                num_compressed += 1;
+                for (total, reduction) in meters.band_reduced.iter_mut().zip(band_reductions[i]) {
+                    *total += reduction / num_channels as f32;
+                }
+            } else {
+                processed[i] = dry;
+                meters.amount_reduced[i] = 1.0;
+                for total in &mut meters.band_reduced {
+                    *total += 1.0 / num_channels as f32;
+                }
+            }
+        }
+        if num_compressed > 0 {
+            let makeup_coeff = calculate_filter_coefficient(AUTO_MAKEUP_TIME, self.sample_rate);
+            self.gain_reduction_average
+                .process(compressed_reduced / num_compressed as f32, makeup_coeff);
+        }
+        if stereo_mode.is_mid_side() {
+            pre_processed = decode_mid_side(pre_processed);
+            processed = decode_mid_side(processed);
+        }
+
+        let (dry_gain, wet_gain) = parallel.blend_mode.gains(parallel.mix);
+        let mut outputs = [0.0; 2];
+        for i in 0..num_channels {
+            // TODO:
+            // might have to play around with the order of operations here
+            let pre_processed = pre_processed[i] * settings.input_gain;
+            meters.pre_amplitude += pre_processed;
+
+            // blend based on dry_wet
+            let blended_output = dry_gain * pre_processed + wet_gain * processed[i];
+
+            // finally, modify with output gain
+            outputs[i] = blended_output * settings.output_gain;
+        }
+
+        // when listening, the filtered key replaces everything else (there's nothing to protect with the limiter)
+        let listening =
+            settings.sidechain_listen || (de_esser.is_enabled() && settings.de_esser_listen);
+        let limiting = settings.limiter_enabled && !listening;
+        if limiting && !self.limiting {
+            // otherwise, the limiter would play out whatever was left in its delay lines when it stopped
+            self.limiter.reset();
+        }
+        self.limiting = limiting;
+        if listening {
+            outputs = if stereo_mode.is_mid_side() {
+                decode_mid_side(keys)
+            } else {
+                keys
+            };
+        } else if limiting {
+            // the limiter has to see every channel at once, since they're all turned down together
+            let ceiling = util::db_to_gain(settings.limiter_ceiling);
+            let release = calculate_filter_coefficient(settings.limiter_release, self.sample_rate);
+            self.limiter
+                .process(&mut outputs[..num_channels], ceiling, release);
+        }
+
+        for (sample, output) in frame.iter_mut().zip(outputs) {
+            meters.post_amplitude += output;
+            // and we're done!
+            *sample = output;
+        }
+
+        meters.pre_amplitude = (meters.pre_amplitude / num_channels as f32).abs();
+        meters.post_amplitude = (meters.post_amplitude / num_channels as f32).abs();
+        meters
+    }
+}
+
+/// Converts a pair of L/R samples to M/S.
+fn encode_mid_side([left, right]: [f32; 2]) -> [f32; 2] {
+    [0.5 * (left + right), 0.5 * (left - right)]
+}
+/// Converts a pair of M/S samples back to L/R. This is the exact inverse of `encode_mid_side()`.
+fn decode_mid_side([mid, side]: [f32; 2]) -> [f32; 2] {
+    [mid + side, mid - side]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::preset::Preset;
+
+    const SAMPLE_RATE: f32 = 48_000.0;
+
+    /// The plugin's default settings, except that the threshold is too high for any of the test signals to reach.
+    fn settings(num_channels: usize) -> ProcessorSettings {
+        let mut preset = Preset::default();
+        preset.settings.curve.threshold = 0.0;
+        preset.processor_settings(num_channels)
+    }
+
+    /// A quiet sine, well below the threshold of `settings()`.
+    fn sine(i: usize) -> f32 {
+        0.1 * (i as f32 * 0.05).sin()
+    }
+
+    /// What the `ParallelPath` does to the dry signal with the default settings. It's an allpass, which everything goes through.
+    fn allpassed(input: impl IntoIterator<Item = f32>) -> Vec<f32> {
+        let mut path = ParallelPath::default();
+        let settings = ParallelSettings::default();
+        input
+            .into_iter()
+            .map(|sample| path.process(sample, sample, &settings, SAMPLE_RATE).0)
+            .collect()
+    }
+
+    /// Runs a single channel through the processor, and returns the output.
+    fn run_mono(
+        processor: &mut Processor,
+        settings: &ProcessorSettings,
+        input: &[f32],
+    ) -> Vec<f32> {
+        input
+            .iter()
+            .map(|sample| {
+                let mut frame = [*sample];
+                processor.process(&mut frame, None, settings);
+                frame[0]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn latency_matches_impulse_delay() {
+        // 0.005 s isn't a whole number of samples in floating point, and 0.0101 s isn't one at all
+        for lookahead in [0.0625, 0.005, 0.0101] {
+            for oversampling in [1, 2, 4, 8] {
+                for limiter_enabled in [false, true] {
+                    let mut processor = Processor::new(SAMPLE_RATE);
+                    processor.set_oversampling(oversampling);
+                    let mut settings = settings(1);
+                    settings.compressor.lookahead = lookahead;
+                    settings.limiter_enabled = limiter_enabled;
+
+                    let mut input = vec![0.0; 4_000];
+                    input[0] = 0.1;
+                    let output = run_mono(&mut processor, &settings, &input);
+                    let peak = (0..output.len())
+                        .max_by(|a, b| output[*a].abs().total_cmp(&output[*b].abs()))
+                        .unwrap();
+                    assert_eq!(
+                        peak as u32,
+                        processor.latency(lookahead, limiter_enabled),
+                        "{lookahead} s, {oversampling}x, limiter {limiter_enabled}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn dry_and_wet_line_up() {
+        for oversampling in [1, 4] {
+            let mut processor = Processor::new(SAMPLE_RATE);
+            processor.set_oversampling(oversampling);
+            let mut settings = settings(1);
+            settings.compressor.lookahead = 0.0625;
+            // nothing is being compressed, so half of each is only the same as either one if they're lined up
+            settings.parallel.mix = 0.5;
+
+            let input: Vec<f32> = (0..8_000).map(sine).collect();
+            let output = run_mono(&mut processor, &settings, &input);
+            let latency = processor.latency(settings.compressor.lookahead, false) as usize;
+            let expected = allpassed(input);
+            for (i, (output, expected)) in output[latency..].iter().zip(expected).enumerate() {
+                assert!(
+                    (output - expected).abs() < 1e-3,
+                    "{oversampling}x, sample {i}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn listening_plays_the_filtered_key() {
+        let mut processor = Processor::new(SAMPLE_RATE);
+        let mut sidechain = settings(2);
+        sidechain.sidechain_source = SidechainSource::External;
+        sidechain.sidechain_listen = true;
+        // neither of these should get in the way, the key comes out as-is and right away
+        sidechain.stereo_mode = StereoMode::MidSide;
+        sidechain.limiter_enabled = true;
+        sidechain.compressor.lookahead = 0.01;
+        for i in 0..4_800 {
+            let aux = [sine(i), 0.5 * sine(i + 100)];
+            let mut frame = [0.0, sine(i)];
+            processor.process(&mut frame, Some(&aux), &sidechain);
+            for (output, key) in frame.iter().zip(aux) {
+                assert!((output - key).abs() < 1e-6, "sample {i}");
+            }
+        }
+
+        // the de-esser listens to its own band-pass instead of the sidechain filter
+        let mut processor = Processor::new(SAMPLE_RATE);
+        let mut de_essing = settings(1);
+        de_essing.de_esser.mode = DeEsserMode::Wideband;
+        de_essing.de_esser_listen = true;
+        let mut filter = SidechainFilter::default();
+        for i in 0..4_800 {
+            let mut frame = [sine(i * 7)];
+            processor.process(&mut frame, None, &de_essing);
+            let expected = filter.process(
+                sine(i * 7),
+                &de_essing.de_esser.sidechain_filter(),
+                SAMPLE_RATE,
+            );
+            assert!((frame[0] - expected).abs() < 1e-6, "sample {i}");
+        }
+    }
+
+    #[test]
+    fn external_key_ducks_the_main_input() {
+        let mut processor = Processor::new(SAMPLE_RATE);
+        let mut settings = settings(1);
+        settings.compressor.curve.threshold = -20.0;
+        settings.sidechain_source = SidechainSource::External;
+        let mut reduced = 1.0;
+        for i in 0..4_800 {
+            // the main input is too quiet to be compressed on its own, but the aux isn't
+            let mut frame = [0.1 * sine(i)];
+            reduced = processor
+                .process(&mut frame, Some(&[8.0 * sine(i)]), &settings)
+                .amount_reduced[0];
+        }
+        assert!(reduced < 0.5, "{reduced}");
+
+        // the same main input with a quiet aux isn't touched
+        let mut processor = Processor::new(SAMPLE_RATE);
+        for i in 0..4_800 {
+            let mut frame = [0.1 * sine(i)];
+            reduced = processor
+                .process(&mut frame, Some(&[0.1 * sine(i)]), &settings)
+                .amount_reduced[0];
+        }
+        assert_eq!(reduced, 1.0);
+    }
+
+    #[test]
+    fn blended_key_is_the_average_of_both_inputs() {
+        let mut processor = Processor::new(SAMPLE_RATE);
+        let mut settings = settings(2);
+        settings.sidechain_source = SidechainSource::Blend;
+        // listening plays the key, so that's easy to check
+        settings.sidechain_listen = true;
+        for i in 0..4_800 {
+            let main = [sine(i), sine(i + 50)];
+            let aux = [sine(2 * i), -sine(i)];
+            let mut frame = main;
+            processor.process(&mut frame, Some(&aux), &settings);
+            for channel in 0..2 {
+                let expected = 0.5 * (main[channel] + aux[channel]);
+                assert!((frame[channel] - expected).abs() < 1e-6, "sample {i}");
+            }
+        }
+    }
+
+    #[test]
+    fn missing_aux_channels_fall_back_to_the_main_input() {
+        for source in [SidechainSource::External, SidechainSource::Blend] {
+            let mut processor = Processor::new(SAMPLE_RATE);
+            let mut settings = settings(2);
+            settings.sidechain_source = source;
+            settings.sidechain_listen = true;
+            for i in 0..4_800 {
+                let main = [sine(i), sine(i + 50)];
+
+                // without an aux, both channels are keyed by the main input
+                let mut frame = main;
+                processor.process(&mut frame, None, &settings);
+                assert_eq!(frame, main, "{source:?}, sample {i}");
+
+                // with a mono aux, only the first channel uses it
+                let mut frame = main;
+                processor.process(&mut frame, Some(&[sine(2 * i)]), &settings);
+                assert_eq!(frame[1], main[1], "{source:?}, sample {i}");
+            }
+        }
+    }
+
+    #[test]
+    fn lookahead_can_be_longer_than_the_rms_window() {
+        let mut processor = Processor::new(SAMPLE_RATE);
+        processor.set_rms_buffer_size(0.01);
+        let mut settings = settings(1);
+        settings.compressor.curve.threshold = -20.0;
+        settings.compressor.lookahead = 0.08;
+        // the latency is just the lookahead, no matter how long the RMS window is
+        assert_eq!(processor.latency(0.08, false), 3_840);
+        processor.set_rms_buffer_size(0.05);
+        assert_eq!(processor.latency(0.08, false), 3_840);
+        processor.set_rms_buffer_size(0.01);
+
+        // a quiet sine with a loud burst starting at `transient`
+        let transient = 9_600;
+        let input: Vec<f32> = (0..transient + 4_800)
+            .map(|i| {
+                if i < transient {
+                    sine(i)
+                } else {
+                    8.0 * sine(i)
+                }
+            })
+            .collect();
+        let output = run_mono(&mut processor, &settings, &input);
+        // the same thing without any compression
+        settings.compressor.curve.threshold = 0.0;
+        let uncompressed = run_mono(&mut Processor::new(SAMPLE_RATE), &settings, &input);
+        let gain = |i: usize| {
+            let window = i - 48 + 3_840..i + 3_840;
+            let peak = |samples: &[f32]| samples.iter().fold(0.0, |peak: f32, x| peak.max(x.abs()));
+            peak(&output[window.clone()]) / peak(&uncompressed[window])
+        };
+        // long before the transient, nothing happens
+        assert!((gain(transient - 4_800) - 1.0).abs() < 1e-3);
+        // the gain is already coming down while the audio is still quiet, so the transient itself is caught
+        assert!(gain(transient - 240) < 0.9, "{}", gain(transient - 240));
+        assert!(gain(transient + 48) < 0.5, "{}", gain(transient + 48));
+    }
+
+    #[test]
+    fn mid_side_round_trip_is_transparent() {
+        let mut processor = Processor::new(SAMPLE_RATE);
+        let mut settings = settings(2);
+        settings.stereo_mode = StereoMode::MidSide;
+        let expected = [
+            allpassed((0..4_800).map(sine)),
+            allpassed((0..4_800).map(|i| 0.5 * sine(3 * i))),
+        ];
+        for i in 0..4_800 {
+            let mut frame = [sine(i), 0.5 * sine(3 * i)];
+            processor.process(&mut frame, None, &settings);
+            for (output, expected) in frame.iter().zip(&expected) {
+                assert!((output - expected[i]).abs() < 1e-6, "sample {i}");
+            }
+        }
+    }
+
+    #[test]
+    fn mid_side_offsets_only_touch_their_own_channel() {
+        for compressed in [0, 1] {
+            let mut processor = Processor::new(SAMPLE_RATE);
+            let mut settings = settings(2);
+            settings.stereo_mode = StereoMode::MidSide;
+            // only one of the channels goes down far enough to be compressed
+            settings.mid_side_offsets[compressed] = (-40.0, 0.0);
+            let mut meters = FrameMeters::default();
+            for i in 0..4_800 {
+                // plenty of both mid and side
+                meters = processor.process(&mut [4.0 * sine(i), -2.0 * sine(i)], None, &settings);
+            }
+            assert!(meters.amount_reduced[compressed] < 0.5);
+            assert_eq!(meters.amount_reduced[1 - compressed], 1.0);
+        }
+    }
+
+    #[test]
+    fn switching_bands_doesnt_replay_old_audio() {
+        let mut processor = Processor::new(SAMPLE_RATE);
+        let mut settings = settings(2);
+        // a long lookahead, so that there's plenty of old audio in the delay lines to replay
+        settings.compressor.lookahead = 0.05;
+        let mut run = |processor: &mut Processor, num_bands: usize, loud: bool| {
+            settings.multiband.num_bands = num_bands;
+            (0..4_800)
+                .map(|i| {
+                    let mut frame = [if loud { sine(i) } else { 0.0 }; 2];
+                    processor.process(&mut frame, None, &settings);
+                    frame[0].abs().max(frame[1].abs())
+                })
+                // the parallel path's allpass rings out for a moment, but anything replayed would go on for the whole lookahead
+                .skip(240)
+                .fold(0.0, f32::max)
+        };
+
+        // get some audio into both the full-band and the multiband compressors...
+        assert!(run(&mut processor, 1, true) > 0.05);
+        assert!(run(&mut processor, 2, true) > 0.05);
+        // ...and then switch over with silence coming in, which is all we should hear
+        assert!(run(&mut processor, 1, false) < 1e-6);
+        assert!(run(&mut processor, 3, false) < 1e-6);
+    }
+
+    #[test]
+    fn limiter_starts_clean_when_switched_back_on() {
+        let mut processor = Processor::new(SAMPLE_RATE);
+        let mut settings = settings(2);
+        settings.limiter_enabled = true;
+        // something loud enough to get the limiter working hard, and to fill its delay lines...
+        for i in 0..4_800 {
+            processor.process(&mut [4.0 * sine(i); 2], None, &settings);
+        }
+        // ...which it doesn't hear while it's off
+        settings.limiter_enabled = false;
+        for i in 0..4_800 {
+            processor.process(&mut [4.0 * sine(i); 2], None, &settings);
+        }
+
+        settings.limiter_enabled = true;
+        let latency = processor.latency(0.0, true) as usize;
+        // everything the limiter gets has been through the parallel path's allpass, which still remembers the loud part
+        let loud = (0..4_800).map(|i| 4.0 * sine(i));
+        let expected = allpassed(loud.clone().chain(loud).chain((0..4_800).map(sine)));
+        for i in 0..4_800 {
+            let mut frame = [sine(i); 2];
+            processor.process(&mut frame, None, &settings);
+            // no leftover audio, and no leftover gain reduction either
+            let expected = if i < latency {
+                0.0
+            } else {
+                expected[9_600 + i - latency]
+            };
+            for output in frame {
+                assert!((output - expected).abs() < 1e-6, "sample {i}: got {output}");
+            }
+        }
+    }
+}